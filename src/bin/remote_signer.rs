@@ -0,0 +1,30 @@
+//! Reference signing service for `RemoteSigner`, holding a single key in memory.
+//!
+//! Configured through the environment:
+//! - `REMOTE_SIGNER_SECRET`: shared secret used to authenticate requests
+//! - `REMOTE_SIGNER_MNEMONIC`: mnemonic of the signing key
+//! - `REMOTE_SIGNER_DERIVATION_PATH`: defaults to `m/44'/118'/0'/0/0`
+//! - `REMOTE_SIGNER_ADDR`: defaults to `127.0.0.1:8080`
+
+use std::env;
+use std::error::Error;
+use std::net::TcpListener;
+
+use cosm_utils::signing_key::{remote::serve, signer::LocalSigner};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let secret = env::var("REMOTE_SIGNER_SECRET").map_err(|_| "REMOTE_SIGNER_SECRET is not set")?;
+    let mnemonic =
+        env::var("REMOTE_SIGNER_MNEMONIC").map_err(|_| "REMOTE_SIGNER_MNEMONIC is not set")?;
+    let derivation_path = env::var("REMOTE_SIGNER_DERIVATION_PATH")
+        .unwrap_or_else(|_| "m/44'/118'/0'/0/0".to_string());
+    let addr = env::var("REMOTE_SIGNER_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+
+    let signer = LocalSigner::from_mnemonic(&mnemonic, &derivation_path)?;
+    let listener = TcpListener::bind(&addr)?;
+    println!("remote signer listening on {addr}");
+
+    serve(listener, signer, secret).await?;
+    Ok(())
+}
@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use cosmrs::proto::cosmos::bank::v1beta1::MsgSend;
+use cosmrs::proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmrs::proto::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmrs::proto::cosmos::staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate};
+use cosmrs::proto::cosmwasm::wasm::v1::{
+    MsgExecuteContract, MsgInstantiateContract, MsgMigrateContract,
+};
+use cosmrs::proto::traits::{Message, TypeUrl};
+use cosmrs::Any;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::error::ChainError;
+use super::fee::Fee;
+
+/// Converts the proto message in an [`Any`] to its amino JSON `value`
+pub type AminoEncoder = fn(&Any) -> Result<Value, ChainError>;
+
+/// Maps proto type urls to their amino names and JSON encoders, for `SIGN_MODE_LEGACY_AMINO_JSON`
+#[derive(Clone, Debug)]
+pub struct AminoRegistry {
+    types: HashMap<String, (String, AminoEncoder)>,
+}
+
+impl AminoRegistry {
+    /// A registry without any messages, see [`AminoRegistry::default`] for the built in ones
+    pub fn new() -> Self {
+        Self {
+            types: HashMap::new(),
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        type_url: impl Into<String>,
+        amino_name: impl Into<String>,
+        encoder: AminoEncoder,
+    ) {
+        self.types
+            .insert(type_url.into(), (amino_name.into(), encoder));
+    }
+
+    pub fn to_amino(&self, any: &Any) -> Result<AminoMsg, ChainError> {
+        let (name, encoder) =
+            self.types
+                .get(&any.type_url)
+                .ok_or_else(|| ChainError::UnregisteredAminoType {
+                    type_url: any.type_url.clone(),
+                })?;
+
+        Ok(AminoMsg {
+            kind: name.clone(),
+            value: encoder(any)?,
+        })
+    }
+}
+
+impl Default for AminoRegistry {
+    /// Bank, cosmwasm, staking and distribution messages
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(MsgSend::TYPE_URL, "cosmos-sdk/MsgSend", encode_msg_send);
+        registry.register(
+            MsgExecuteContract::TYPE_URL,
+            "wasm/MsgExecuteContract",
+            encode_msg_execute_contract,
+        );
+        registry.register(
+            MsgInstantiateContract::TYPE_URL,
+            "wasm/MsgInstantiateContract",
+            encode_msg_instantiate_contract,
+        );
+        registry.register(
+            MsgMigrateContract::TYPE_URL,
+            "wasm/MsgMigrateContract",
+            encode_msg_migrate_contract,
+        );
+        registry.register(
+            MsgDelegate::TYPE_URL,
+            "cosmos-sdk/MsgDelegate",
+            encode_msg_delegate,
+        );
+        registry.register(
+            MsgUndelegate::TYPE_URL,
+            "cosmos-sdk/MsgUndelegate",
+            encode_msg_undelegate,
+        );
+        registry.register(
+            MsgBeginRedelegate::TYPE_URL,
+            "cosmos-sdk/MsgBeginRedelegate",
+            encode_msg_begin_redelegate,
+        );
+        registry.register(
+            MsgWithdrawDelegatorReward::TYPE_URL,
+            "cosmos-sdk/MsgWithdrawDelegationReward",
+            encode_msg_withdraw_delegator_reward,
+        );
+        registry
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<AminoRegistry> = RwLock::new(AminoRegistry::default());
+}
+
+/// Registers a message type in the registry used when signing with amino JSON
+pub fn register_amino_msg(
+    type_url: impl Into<String>,
+    amino_name: impl Into<String>,
+    encoder: AminoEncoder,
+) {
+    REGISTRY
+        .write()
+        .unwrap()
+        .register(type_url, amino_name, encoder);
+}
+
+/// Converts `any` with the registry used when signing with amino JSON
+pub fn to_amino_msg(any: &Any) -> Result<AminoMsg, ChainError> {
+    REGISTRY.read().unwrap().to_amino(any)
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct AminoMsg {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct StdFee {
+    pub amount: Vec<Value>,
+    pub gas: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub payer: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub granter: String,
+}
+
+/// The document signed in `SIGN_MODE_LEGACY_AMINO_JSON`, `StdSignDoc` in the cosmos-sdk
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct StdSignDoc {
+    pub account_number: String,
+    pub chain_id: String,
+    pub fee: StdFee,
+    pub memo: String,
+    pub msgs: Vec<AminoMsg>,
+    pub sequence: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_height: Option<String>,
+}
+
+impl StdSignDoc {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        msgs: &[Any],
+        timeout_height: u64,
+        memo: &str,
+        account_number: u64,
+        sequence: u64,
+        fee: &Fee,
+        chain_id: &str,
+    ) -> Result<Self, ChainError> {
+        Ok(Self {
+            account_number: account_number.to_string(),
+            chain_id: chain_id.to_string(),
+            fee: StdFee {
+                amount: fee
+                    .amount
+                    .iter()
+                    .map(|c| json!({"amount": c.amount.to_string(), "denom": c.denom.to_string()}))
+                    .collect(),
+                gas: fee.gas_limit.value().to_string(),
+                payer: fee
+                    .payer
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                granter: fee
+                    .granter
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            },
+            memo: memo.to_string(),
+            msgs: msgs.iter().map(to_amino_msg).collect::<Result<_, _>>()?,
+            sequence: sequence.to_string(),
+            timeout_height: (timeout_height != 0).then(|| timeout_height.to_string()),
+        })
+    }
+
+    /// Canonical JSON with sorted keys, byte for byte what the cosmos-sdk signs
+    pub fn to_sign_bytes(&self) -> Result<Vec<u8>, ChainError> {
+        let value = serde_json::to_value(self).map_err(ChainError::amino_json)?;
+        Ok(sorted_json(&value).into_bytes())
+    }
+}
+
+/// Serializes `value` with sorted object keys and go's `json.Marshal` html escaping
+pub fn sorted_json(value: &Value) -> String {
+    go_escape(&sort_keys(value).to_string())
+}
+
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), sort_keys(v)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(sort_keys).collect()),
+        _ => value.clone(),
+    }
+}
+
+// these characters can only appear inside JSON strings, so escaping them never changes the structure
+fn go_escape(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => escaped.push_str("\\u003c"),
+            '>' => escaped.push_str("\\u003e"),
+            '&' => escaped.push_str("\\u0026"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn decode<M: Message + Default>(any: &Any) -> Result<M, ChainError> {
+    M::decode(any.value.as_slice()).map_err(ChainError::prost_proto_decoding)
+}
+
+fn coin(coin: &ProtoCoin) -> Value {
+    json!({"amount": coin.amount, "denom": coin.denom})
+}
+
+fn coins(coins: &[ProtoCoin]) -> Value {
+    Value::Array(coins.iter().map(coin).collect())
+}
+
+fn contract_msg(msg: &[u8]) -> Result<Value, ChainError> {
+    serde_json::from_slice(msg)
+        .map_err(|e| ChainError::amino_json(format!("contract msg is not valid JSON: {e}")))
+}
+
+fn encode_msg_send(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgSend = decode(any)?;
+    Ok(json!({
+        "amount": coins(&msg.amount),
+        "from_address": msg.from_address,
+        "to_address": msg.to_address,
+    }))
+}
+
+fn encode_msg_execute_contract(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgExecuteContract = decode(any)?;
+    Ok(json!({
+        "contract": msg.contract,
+        "funds": coins(&msg.funds),
+        "msg": contract_msg(&msg.msg)?,
+        "sender": msg.sender,
+    }))
+}
+
+fn encode_msg_instantiate_contract(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgInstantiateContract = decode(any)?;
+    let mut value = json!({
+        "code_id": msg.code_id.to_string(),
+        "funds": coins(&msg.funds),
+        "label": msg.label,
+        "msg": contract_msg(&msg.msg)?,
+        "sender": msg.sender,
+    });
+    if !msg.admin.is_empty() {
+        value["admin"] = Value::String(msg.admin);
+    }
+    Ok(value)
+}
+
+fn encode_msg_migrate_contract(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgMigrateContract = decode(any)?;
+    Ok(json!({
+        "code_id": msg.code_id.to_string(),
+        "contract": msg.contract,
+        "msg": contract_msg(&msg.msg)?,
+        "sender": msg.sender,
+    }))
+}
+
+fn encode_msg_delegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgDelegate = decode(any)?;
+    Ok(json!({
+        "amount": msg.amount.as_ref().map(coin),
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+    }))
+}
+
+fn encode_msg_undelegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgUndelegate = decode(any)?;
+    Ok(json!({
+        "amount": msg.amount.as_ref().map(coin),
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+    }))
+}
+
+fn encode_msg_begin_redelegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgBeginRedelegate = decode(any)?;
+    Ok(json!({
+        "amount": msg.amount.as_ref().map(coin),
+        "delegator_address": msg.delegator_address,
+        "validator_dst_address": msg.validator_dst_address,
+        "validator_src_address": msg.validator_src_address,
+    }))
+}
+
+fn encode_msg_withdraw_delegator_reward(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgWithdrawDelegatorReward = decode(any)?;
+    Ok(json!({
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmrs::proto::traits::MessageExt;
+
+    use super::*;
+    use crate::chain::coin::Coin;
+    use crate::modules::auth::model::Address;
+
+    // msgs are the `aminomessages.spec.ts` cases of cosmjs v0.31 (stargate bank and staking,
+    // cosmwasm-stargate), the sign doc around them is the one of the `TestStdSignBytes` cases
+    // in `x/auth/migrations/legacytx` of the cosmos-sdk v0.47
+
+    const ADDRESS: &str = "cosmos1pkptre7fdkl6gfrzlesjjvhxhlc3r4gmmk8rs6";
+    const OTHER: &str = "cosmos10dyr9899g6t0pelew4nvf4j5c3jcgv0r73qga5";
+    const CONTRACT: &str = "cosmos1xy4yqngt0nlkdcenxymg8tenrghmek4nmqm28k";
+
+    fn ucosm(amount: &str) -> ProtoCoin {
+        ProtoCoin {
+            denom: "ucosm".to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    fn sdk_sign_bytes(
+        msg: Any,
+        timeout_height: u64,
+        payer: Option<&str>,
+        granter: Option<&str>,
+    ) -> String {
+        let fee = Fee::new(
+            Coin {
+                denom: "atom".parse().unwrap(),
+                amount: 150,
+            },
+            100_000u64,
+            payer.map(|payer| payer.parse::<Address>().unwrap()),
+            granter.map(|granter| granter.parse::<Address>().unwrap()),
+        );
+        let doc = StdSignDoc::new(&[msg], timeout_height, "memo", 3, 6, &fee, "1234").unwrap();
+        String::from_utf8(doc.to_sign_bytes().unwrap()).unwrap()
+    }
+
+    fn bank_send() -> Any {
+        MsgSend {
+            from_address: ADDRESS.to_string(),
+            to_address: OTHER.to_string(),
+            amount: vec![ucosm("1234")],
+        }
+        .to_any()
+        .unwrap()
+    }
+
+    #[test]
+    fn cosmjs_bank_send() {
+        assert_eq!(
+            to_amino_msg(&bank_send()).unwrap(),
+            AminoMsg {
+                kind: "cosmos-sdk/MsgSend".to_string(),
+                value: json!({
+                    "from_address": ADDRESS,
+                    "to_address": OTHER,
+                    "amount": [{"amount": "1234", "denom": "ucosm"}],
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn cosmjs_staking_delegate() {
+        let msg = MsgDelegate {
+            delegator_address: ADDRESS.to_string(),
+            validator_address: OTHER.to_string(),
+            amount: Some(ucosm("1234")),
+        };
+        assert_eq!(
+            to_amino_msg(&msg.to_any().unwrap()).unwrap(),
+            AminoMsg {
+                kind: "cosmos-sdk/MsgDelegate".to_string(),
+                value: json!({
+                    "delegator_address": ADDRESS,
+                    "validator_address": OTHER,
+                    "amount": {"amount": "1234", "denom": "ucosm"},
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn cosmjs_wasm_execute() {
+        let msg = MsgExecuteContract {
+            sender: ADDRESS.to_string(),
+            contract: CONTRACT.to_string(),
+            msg: br#"{"foo":"bar"}"#.to_vec(),
+            funds: vec![ucosm("1234")],
+        };
+        assert_eq!(
+            to_amino_msg(&msg.to_any().unwrap()).unwrap(),
+            AminoMsg {
+                kind: "wasm/MsgExecuteContract".to_string(),
+                value: json!({
+                    "sender": ADDRESS,
+                    "contract": CONTRACT,
+                    "msg": {"foo": "bar"},
+                    "funds": [{"amount": "1234", "denom": "ucosm"}],
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn sdk_sign_doc() {
+        let msg = format!(
+            r#"{{"type":"cosmos-sdk/MsgSend","value":{{"amount":[{{"amount":"1234","denom":"ucosm"}}],"from_address":"{ADDRESS}","to_address":"{OTHER}"}}}}"#
+        );
+        assert_eq!(
+            sdk_sign_bytes(bank_send(), 10, None, None),
+            format!(
+                r#"{{"account_number":"3","chain_id":"1234","fee":{{"amount":[{{"amount":"150","denom":"atom"}}],"gas":"100000"}},"memo":"memo","msgs":[{msg}],"sequence":"6","timeout_height":"10"}}"#
+            )
+        );
+        assert_eq!(
+            sdk_sign_bytes(bank_send(), 0, Some(OTHER), None),
+            format!(
+                r#"{{"account_number":"3","chain_id":"1234","fee":{{"amount":[{{"amount":"150","denom":"atom"}}],"gas":"100000","payer":"{OTHER}"}},"memo":"memo","msgs":[{msg}],"sequence":"6"}}"#
+            )
+        );
+        assert_eq!(
+            sdk_sign_bytes(bank_send(), 0, None, Some(OTHER)),
+            format!(
+                r#"{{"account_number":"3","chain_id":"1234","fee":{{"amount":[{{"amount":"150","denom":"atom"}}],"gas":"100000","granter":"{OTHER}"}},"memo":"memo","msgs":[{msg}],"sequence":"6"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn sdk_sign_doc_execute_and_delegate() {
+        let execute = MsgExecuteContract {
+            sender: ADDRESS.to_string(),
+            contract: CONTRACT.to_string(),
+            msg: br#"{"foo":"bar"}"#.to_vec(),
+            funds: vec![ucosm("1234")],
+        };
+        assert_eq!(
+            sdk_sign_bytes(execute.to_any().unwrap(), 0, None, None),
+            format!(
+                r#"{{"account_number":"3","chain_id":"1234","fee":{{"amount":[{{"amount":"150","denom":"atom"}}],"gas":"100000"}},"memo":"memo","msgs":[{{"type":"wasm/MsgExecuteContract","value":{{"contract":"{CONTRACT}","funds":[{{"amount":"1234","denom":"ucosm"}}],"msg":{{"foo":"bar"}},"sender":"{ADDRESS}"}}}}],"sequence":"6"}}"#
+            )
+        );
+
+        let delegate = MsgDelegate {
+            delegator_address: ADDRESS.to_string(),
+            validator_address: OTHER.to_string(),
+            amount: Some(ucosm("1234")),
+        };
+        assert_eq!(
+            sdk_sign_bytes(delegate.to_any().unwrap(), 0, None, None),
+            format!(
+                r#"{{"account_number":"3","chain_id":"1234","fee":{{"amount":[{{"amount":"150","denom":"atom"}}],"gas":"100000"}},"memo":"memo","msgs":[{{"type":"cosmos-sdk/MsgDelegate","value":{{"amount":{{"amount":"1234","denom":"ucosm"}},"delegator_address":"{ADDRESS}","validator_address":"{OTHER}"}}}}],"sequence":"6"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn contract_msgs_are_html_escaped() {
+        // go's `json.Marshal` escapes these, so the sdk signs the escaped form
+        let msg = MsgExecuteContract {
+            sender: ADDRESS.to_string(),
+            contract: CONTRACT.to_string(),
+            msg: br#"{"recipient":"<&>"}"#.to_vec(),
+            funds: vec![],
+        };
+        assert!(sdk_sign_bytes(msg.to_any().unwrap(), 0, None, None)
+            .contains(r#""msg":{"recipient":"\u003c\u0026\u003e"}"#));
+    }
+
+    #[test]
+    fn unregistered_type_is_rejected() {
+        let any = Any {
+            type_url: "/example.v1.MsgUnknown".to_string(),
+            value: vec![],
+        };
+        assert!(matches!(
+            to_amino_msg(&any),
+            Err(ChainError::UnregisteredAminoType { .. })
+        ));
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn amino_json_signed_tx_is_accepted() {
+        use crate::chain::request::{TxOptions, TxSignMode};
+        use crate::clients::fake::FakeChain;
+        use crate::modules::bank::api::BankTxCommit;
+
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+        let tx_options = TxOptions {
+            memo: "amino".to_string(),
+            sign_mode: TxSignMode::LegacyAminoJson,
+            ..Default::default()
+        };
+        chain
+            .bank_send_commit(&cfg, req, &key, &tx_options)
+            .await
+            .unwrap();
+        assert_eq!(chain.account(&sender).unwrap().sequence, 1);
+    }
+}
@@ -2,9 +2,12 @@ use cosmrs::proto::cosmos::base::abci::v1beta1::Result;
 use cosmrs::proto::prost::{DecodeError, EncodeError};
 use cosmrs::tendermint::Hash;
 use cosmrs::ErrorReport;
+use std::path::{Path, PathBuf};
 use tendermint_rpc::endpoint::abci_query::AbciQuery;
 use thiserror::Error;
 
+use crate::modules::auth::model::Address;
+
 #[cfg(feature = "keyring")]
 pub use keyring::Error as KeyringError;
 
@@ -28,6 +31,50 @@ pub enum ChainError {
     #[error("invalid derivation path")]
     DerviationPath,
 
+    #[error("signer does not support sign mode {mode}")]
+    UnsupportedSignMode { mode: String },
+
+    #[cfg(feature = "remote-signer")]
+    #[error("remote signer error: {message}")]
+    RemoteSigner { message: String },
+
+    #[error("key {name:?} is not derived from a mnemonic")]
+    NotMnemonic { name: String },
+
+    #[error("key {name:?} already exists")]
+    KeyExists { name: String },
+
+    #[error("key {name:?} not found")]
+    KeyNotFound { name: String },
+
+    #[cfg(feature = "keyring")]
+    #[error("invalid keyring index: {message}")]
+    KeyringIndex { message: String },
+
+    #[error("keystore error: {message}")]
+    Keystore { message: String },
+
+    #[error("no amino JSON encoding registered for {type_url}")]
+    UnregisteredAminoType { type_url: String },
+
+    #[error("amino JSON encoding error: {message}")]
+    AminoJson { message: String },
+
+    #[error("signers of {type_url} are unknown, register them with `register_msg_signers`")]
+    UnknownMsgSigners { type_url: String },
+
+    #[error("tx signers do not match: {message}")]
+    TxSigners { message: String },
+
+    #[error("tx JSON error: {message}")]
+    TxJson { message: String },
+
+    #[error("invalid tx signatures: {}", join(.failures))]
+    InvalidSignatures { failures: Vec<SignatureFailure> },
+
+    #[error("multisig error: {message}")]
+    Multisig { message: String },
+
     #[error("cryptographic error: {message:?}")]
     Crypto { message: String },
 
@@ -46,14 +93,43 @@ pub enum ChainError {
     #[error("tx_search timed out looking for: {tx_hash:?}")]
     TxSearchTimeout { tx_hash: Hash },
 
+    #[error("tx {tx_hash:?} was not included before its timeout height {timeout_height}, chain is at height {height}")]
+    TxTimeoutHeightExceeded {
+        tx_hash: Hash,
+        timeout_height: u64,
+        height: u64,
+    },
+
+    #[error("unexpected event received for subscription: {query}")]
+    UnexpectedEvent { query: String },
+
+    #[error("subscription closed: {query}")]
+    SubscriptionClosed { query: String },
+
+    #[error("checkpoint error for {path:?}: {message}")]
+    Checkpoint { path: PathBuf, message: String },
+
+    #[error("fixture error: {message}")]
+    Fixture { message: String },
+
+    #[error("recorded error: {message}")]
+    Recorded { message: String },
+
+    /// `code` and `log` are those of CheckTx, or of DeliverTx when `delivered`,
+    /// in which case the tx was included in a block and used up its sequence
     #[error("tx_commit error: {res}")]
-    TxCommit { res: String },
+    TxCommit {
+        res: String,
+        code: u32,
+        log: String,
+        delivered: bool,
+    },
 
     #[error("tx_sync error: {res}")]
-    TxSync { res: String },
+    TxSync { res: String, code: u32, log: String },
 
     #[error("tx_async error: {res}")]
-    TxAsync { res: String },
+    TxAsync { res: String, code: u32, log: String },
 
     #[cfg(feature = "keyring")]
     #[error(transparent)]
@@ -79,12 +155,62 @@ impl ChainError {
         }
     }
 
+    #[cfg(feature = "remote-signer")]
+    pub(crate) fn remote_signer(e: impl std::fmt::Display) -> ChainError {
+        ChainError::RemoteSigner {
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn keystore(e: impl std::fmt::Display) -> ChainError {
+        ChainError::Keystore {
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn amino_json(e: impl std::fmt::Display) -> ChainError {
+        ChainError::AminoJson {
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn tx_json(e: impl std::fmt::Display) -> ChainError {
+        ChainError::TxJson {
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn multisig(e: impl std::fmt::Display) -> ChainError {
+        ChainError::Multisig {
+            message: e.to_string(),
+        }
+    }
+
     pub(crate) fn proto_encoding(e: ErrorReport) -> ChainError {
         ChainError::ProtoEncoding {
             message: e.to_string(),
         }
     }
 
+    pub(crate) fn proto_decoding(e: ErrorReport) -> ChainError {
+        ChainError::ProtoDecoding {
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn checkpoint(path: &Path, e: impl std::fmt::Display) -> ChainError {
+        ChainError::Checkpoint {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        }
+    }
+
+    pub(crate) fn fixture(path: &Path, e: impl std::fmt::Display) -> ChainError {
+        ChainError::Fixture {
+            message: format!("{}: {}", path.display(), e),
+        }
+    }
+
     pub(crate) fn prost_proto_encoding(e: EncodeError) -> ChainError {
         ChainError::ProtoEncoding {
             message: e.to_string(),
@@ -98,6 +224,50 @@ impl ChainError {
     }
 }
 
+/// A signer of a tx whose signature did not verify, see `Tx::verify_signatures`
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("signer {index} ({address}): {reason}")]
+pub struct SignatureFailure {
+    pub index: usize,
+    pub address: Address,
+    pub reason: SignatureFailureReason,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SignatureFailureReason {
+    #[error("no account given for the signer")]
+    MissingAccount,
+
+    #[error("no public key in the signer info or the account")]
+    MissingPublicKey,
+
+    #[error("invalid public key: {message}")]
+    InvalidPublicKey { message: String },
+
+    #[error("public key does not belong to the signer")]
+    PublicKeyMismatch,
+
+    #[error("unsupported sign mode {mode}")]
+    UnsupportedSignMode { mode: String },
+
+    #[error("cannot rebuild the sign doc: {message}")]
+    SignDoc { message: String },
+
+    #[error("signature does not verify")]
+    InvalidSignature,
+
+    #[error("signed with sequence {got} but the account is at {expected}")]
+    SequenceMismatch { expected: u64, got: u64 },
+}
+
+fn join<T: std::fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Error, Debug)]
 pub enum DeserializeError {
     #[error("Raw chain response is empty")]
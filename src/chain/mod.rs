@@ -1,5 +1,9 @@
 pub mod request;
 
+pub mod amino;
+
+pub mod signers;
+
 pub mod coin;
 
 pub mod fee;
@@ -12,5 +16,7 @@ pub mod response;
 
 pub mod tx;
 
+pub mod tx_json;
+
 pub use cosmrs::proto::traits::Message;
 pub use cosmrs::{proto::traits::TypeUrl, tx::MessageExt, Any};
@@ -104,6 +104,30 @@ pub struct TxOptions {
 
     /// An arbitrary memo to be added to the transaction
     pub memo: String,
+
+    /// How the transaction is signed, `Direct` unless the chain or signer needs amino JSON
+    #[serde(default)]
+    pub sign_mode: TxSignMode,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, Eq, PartialEq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TxSignMode {
+    /// `SIGN_MODE_DIRECT`, signs the protobuf encoded `SignDoc`
+    #[default]
+    Direct,
+    /// `SIGN_MODE_LEGACY_AMINO_JSON`, signs the sorted JSON `StdSignDoc`,
+    /// see [`crate::chain::amino`] to register messages
+    LegacyAminoJson,
+}
+
+impl From<TxSignMode> for cosmrs::tx::SignMode {
+    fn from(mode: TxSignMode) -> Self {
+        match mode {
+            TxSignMode::Direct => cosmrs::tx::SignMode::Direct,
+            TxSignMode::LegacyAminoJson => cosmrs::tx::SignMode::LegacyAminoJson,
+        }
+    }
 }
 
 impl Default for TxOptions {
@@ -113,6 +137,7 @@ impl Default for TxOptions {
             account: None,
             timeout_height: Some(0),
             memo: String::default(),
+            sign_mode: TxSignMode::default(),
         }
     }
 }
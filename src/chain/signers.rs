@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use cosmrs::proto::cosmos::authz::v1beta1::{MsgExec, MsgGrant, MsgRevoke};
+use cosmrs::proto::cosmos::bank::v1beta1::{MsgMultiSend, MsgSend};
+use cosmrs::proto::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmrs::proto::cosmos::feegrant::v1beta1::{MsgGrantAllowance, MsgRevokeAllowance};
+use cosmrs::proto::cosmos::gov::v1 as gov_v1;
+use cosmrs::proto::cosmos::gov::v1beta1::{MsgDeposit, MsgVote};
+use cosmrs::proto::cosmos::staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate};
+use cosmrs::proto::cosmwasm::wasm::v1::{
+    MsgClearAdmin, MsgExecuteContract, MsgInstantiateContract, MsgMigrateContract, MsgStoreCode,
+    MsgUpdateAdmin,
+};
+use cosmrs::proto::ibc::applications::transfer::v1::MsgTransfer;
+use cosmrs::proto::traits::Message;
+use cosmrs::Any;
+use lazy_static::lazy_static;
+
+use crate::modules::auth::model::Address;
+
+use super::error::ChainError;
+use super::fee::Fee;
+
+/// Returns the addresses that have to sign a msg, `GetSigners` in the cosmos-sdk
+pub type SignersFn = fn(&Any) -> Result<Vec<String>, ChainError>;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<String, SignersFn>> = RwLock::new(default_signers());
+}
+
+/// Registers how to find the signers of a msg type, for msgs this crate does not know about
+pub fn register_msg_signers(type_url: impl Into<String>, signers: SignersFn) {
+    REGISTRY.write().unwrap().insert(type_url.into(), signers);
+}
+
+/// The addresses that have to sign `any`
+pub fn msg_signers(any: &Any) -> Result<Vec<Address>, ChainError> {
+    let signers = *REGISTRY.read().unwrap().get(&any.type_url).ok_or_else(|| {
+        ChainError::UnknownMsgSigners {
+            type_url: any.type_url.clone(),
+        }
+    })?;
+
+    signers(any)?
+        .iter()
+        .map(|addr| {
+            addr.parse().map_err(|e| ChainError::ProtoDecoding {
+                message: format!("invalid signer address {addr:?}: {e}"),
+            })
+        })
+        .collect()
+}
+
+/// The signers of a tx in the order its signer infos and signatures have to be in:
+/// the signers of every msg in order of first appearance, then the fee payer if it is not one of them
+pub fn tx_signers(msgs: &[Any], fee: &Fee) -> Result<Vec<Address>, ChainError> {
+    tx_signers_with_default(msgs, fee, None)
+}
+
+/// [`tx_signers`], taking `default` as the only signer of msgs whose signers are not registered
+pub(crate) fn tx_signers_with_default(
+    msgs: &[Any],
+    fee: &Fee,
+    default: Option<&Address>,
+) -> Result<Vec<Address>, ChainError> {
+    let mut signers: Vec<Address> = vec![];
+    for msg in msgs {
+        let msg_signers = match (msg_signers(msg), default) {
+            (Err(ChainError::UnknownMsgSigners { .. }), Some(default)) => vec![default.clone()],
+            (res, _) => res?,
+        };
+        for signer in msg_signers {
+            if !signers.contains(&signer) {
+                signers.push(signer);
+            }
+        }
+    }
+
+    if let Some(payer) = &fee.payer {
+        if !signers.contains(payer) {
+            signers.push(payer.clone());
+        }
+    }
+
+    Ok(signers)
+}
+
+fn decode<M: Message + Default>(any: &Any) -> Result<M, ChainError> {
+    M::decode(any.value.as_slice()).map_err(ChainError::prost_proto_decoding)
+}
+
+fn default_signers() -> HashMap<String, SignersFn> {
+    let signers: Vec<(&str, SignersFn)> = vec![
+        ("/cosmos.bank.v1beta1.MsgSend", |any| {
+            Ok(vec![decode::<MsgSend>(any)?.from_address])
+        }),
+        ("/cosmos.bank.v1beta1.MsgMultiSend", |any| {
+            Ok(decode::<MsgMultiSend>(any)?
+                .inputs
+                .into_iter()
+                .map(|input| input.address)
+                .collect())
+        }),
+        ("/cosmwasm.wasm.v1.MsgStoreCode", |any| {
+            Ok(vec![decode::<MsgStoreCode>(any)?.sender])
+        }),
+        ("/cosmwasm.wasm.v1.MsgInstantiateContract", |any| {
+            Ok(vec![decode::<MsgInstantiateContract>(any)?.sender])
+        }),
+        ("/cosmwasm.wasm.v1.MsgExecuteContract", |any| {
+            Ok(vec![decode::<MsgExecuteContract>(any)?.sender])
+        }),
+        ("/cosmwasm.wasm.v1.MsgMigrateContract", |any| {
+            Ok(vec![decode::<MsgMigrateContract>(any)?.sender])
+        }),
+        ("/cosmwasm.wasm.v1.MsgUpdateAdmin", |any| {
+            Ok(vec![decode::<MsgUpdateAdmin>(any)?.sender])
+        }),
+        ("/cosmwasm.wasm.v1.MsgClearAdmin", |any| {
+            Ok(vec![decode::<MsgClearAdmin>(any)?.sender])
+        }),
+        ("/cosmos.staking.v1beta1.MsgDelegate", |any| {
+            Ok(vec![decode::<MsgDelegate>(any)?.delegator_address])
+        }),
+        ("/cosmos.staking.v1beta1.MsgUndelegate", |any| {
+            Ok(vec![decode::<MsgUndelegate>(any)?.delegator_address])
+        }),
+        ("/cosmos.staking.v1beta1.MsgBeginRedelegate", |any| {
+            Ok(vec![decode::<MsgBeginRedelegate>(any)?.delegator_address])
+        }),
+        (
+            "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+            |any| {
+                Ok(vec![
+                    decode::<MsgWithdrawDelegatorReward>(any)?.delegator_address,
+                ])
+            },
+        ),
+        ("/cosmos.feegrant.v1beta1.MsgGrantAllowance", |any| {
+            Ok(vec![decode::<MsgGrantAllowance>(any)?.granter])
+        }),
+        ("/cosmos.feegrant.v1beta1.MsgRevokeAllowance", |any| {
+            Ok(vec![decode::<MsgRevokeAllowance>(any)?.granter])
+        }),
+        ("/cosmos.authz.v1beta1.MsgGrant", |any| {
+            Ok(vec![decode::<MsgGrant>(any)?.granter])
+        }),
+        ("/cosmos.authz.v1beta1.MsgExec", |any| {
+            Ok(vec![decode::<MsgExec>(any)?.grantee])
+        }),
+        ("/cosmos.authz.v1beta1.MsgRevoke", |any| {
+            Ok(vec![decode::<MsgRevoke>(any)?.granter])
+        }),
+        ("/cosmos.gov.v1beta1.MsgVote", |any| {
+            Ok(vec![decode::<MsgVote>(any)?.voter])
+        }),
+        ("/cosmos.gov.v1beta1.MsgDeposit", |any| {
+            Ok(vec![decode::<MsgDeposit>(any)?.depositor])
+        }),
+        ("/cosmos.gov.v1.MsgSubmitProposal", |any| {
+            Ok(vec![decode::<gov_v1::MsgSubmitProposal>(any)?.proposer])
+        }),
+        ("/cosmos.gov.v1.MsgVote", |any| {
+            Ok(vec![decode::<gov_v1::MsgVote>(any)?.voter])
+        }),
+        ("/cosmos.gov.v1.MsgDeposit", |any| {
+            Ok(vec![decode::<gov_v1::MsgDeposit>(any)?.depositor])
+        }),
+        ("/ibc.applications.transfer.v1.MsgTransfer", |any| {
+            Ok(vec![decode::<MsgTransfer>(any)?.sender])
+        }),
+    ];
+
+    signers
+        .into_iter()
+        .chain(injective_signers())
+        .map(|(type_url, signers)| (type_url.to_string(), signers))
+        .collect()
+}
+
+#[cfg(feature = "injective")]
+fn injective_signers() -> Vec<(&'static str, SignersFn)> {
+    use injective_std::types::injective::exchange::v1beta1::{
+        MsgBatchCreateSpotLimitOrders, MsgBatchUpdateOrders, MsgCreateSpotLimitOrder,
+    };
+
+    vec![
+        (MsgCreateSpotLimitOrder::TYPE_URL, |any| {
+            Ok(vec![decode::<MsgCreateSpotLimitOrder>(any)?.sender])
+        }),
+        (MsgBatchCreateSpotLimitOrders::TYPE_URL, |any| {
+            Ok(vec![decode::<MsgBatchCreateSpotLimitOrders>(any)?.sender])
+        }),
+        (MsgBatchUpdateOrders::TYPE_URL, |any| {
+            Ok(vec![decode::<MsgBatchUpdateOrders>(any)?.sender])
+        }),
+    ]
+}
+
+#[cfg(not(feature = "injective"))]
+fn injective_signers() -> Vec<(&'static str, SignersFn)> {
+    vec![]
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmrs::proto::traits::MessageExt;
+
+    use super::*;
+    use crate::chain::coin::Coin;
+
+    #[test]
+    fn signers_follow_sdk_order() {
+        let alice = Address::new("juno", &[1; 20]).unwrap();
+        let bob = Address::new("juno", &[2; 20]).unwrap();
+        let payer = Address::new("juno", &[3; 20]).unwrap();
+
+        let send = |from: &Address| {
+            MsgSend {
+                from_address: from.to_string(),
+                to_address: alice.to_string(),
+                amount: vec![],
+            }
+            .to_any()
+            .unwrap()
+        };
+        let msgs = vec![send(&bob), send(&alice), send(&bob)];
+
+        let mut fee = Fee::new(
+            Coin {
+                denom: "ujuno".parse().unwrap(),
+                amount: 1,
+            },
+            100u64,
+            Some(alice.clone()),
+            None,
+        );
+        assert_eq!(
+            tx_signers(&msgs, &fee).unwrap(),
+            vec![bob.clone(), alice.clone()]
+        );
+
+        fee.payer = Some(payer.clone());
+        assert_eq!(tx_signers(&msgs, &fee).unwrap(), vec![bob, alice, payer]);
+    }
+
+    #[test]
+    fn ibc_transfer_signer() {
+        let sender = Address::new("juno", &[1; 20]).unwrap();
+        let transfer = Any {
+            type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
+            value: MsgTransfer {
+                source_port: "transfer".to_string(),
+                source_channel: "channel-0".to_string(),
+                sender: sender.to_string(),
+                receiver: "osmo1receiver".to_string(),
+                timeout_timestamp: 1,
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        };
+
+        assert_eq!(msg_signers(&transfer).unwrap(), vec![sender]);
+    }
+
+    #[cfg(feature = "injective")]
+    #[test]
+    fn inj_exchange_signer() {
+        use injective_std::types::injective::exchange::v1beta1::MsgBatchUpdateOrders;
+
+        use crate::chain::msg::IntoAny;
+
+        let sender = Address::new("inj", &[1; 20]).unwrap();
+        let update = MsgBatchUpdateOrders {
+            sender: sender.to_string(),
+            ..Default::default()
+        }
+        .into_any()
+        .unwrap();
+
+        assert_eq!(msg_signers(&update).unwrap(), vec![sender]);
+    }
+}
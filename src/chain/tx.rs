@@ -1,7 +1,16 @@
-use crate::chain::error::ChainError;
+use crate::chain::amino::StdSignDoc;
+use crate::chain::error::{ChainError, SignatureFailure, SignatureFailureReason};
+use crate::chain::fee::Fee;
+use crate::chain::signers::tx_signers;
+use crate::modules::auth::model::{Account, Address};
+use crate::signing_key::algorithm::{public_key_from_any, KeyAlgorithm};
+use crate::signing_key::multisig::{MultisigKey, MULTISIG_TYPE_URL};
+use cosmrs::proto::cosmos::crypto::multisig::v1beta1::{CompactBitArray, MultiSignature};
+use cosmrs::proto::cosmos::tx::v1beta1::{mode_info, AuthInfo, SignDoc, TxBody};
 use cosmrs::proto::traits::MessageExt;
 use cosmrs::proto::{cosmos::tx::v1beta1::TxRaw, traits::Message};
-use cosmrs::tx::Raw;
+use cosmrs::tx::{Raw, SignMode};
+use cosmrs::Any;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct RawTx(TxRaw);
@@ -18,6 +27,328 @@ impl RawTx {
     pub fn to_bytes(&self) -> Result<Vec<u8>, ChainError> {
         self.0.to_bytes().map_err(ChainError::prost_proto_encoding)
     }
+
+    /// Decodes the body and auth info of the transaction.
+    pub fn decode(&self) -> Result<Tx, ChainError> {
+        let body = TxBody::decode(self.0.body_bytes.as_slice())
+            .map_err(ChainError::prost_proto_decoding)?;
+        let auth_info = AuthInfo::decode(self.0.auth_info_bytes.as_slice())
+            .map_err(ChainError::prost_proto_decoding)?;
+        let fee = cosmrs::tx::Fee::try_from(auth_info.fee.clone().unwrap_or_default())?;
+
+        Ok(Tx {
+            msgs: body.messages,
+            memo: body.memo,
+            timeout_height: body.timeout_height,
+            fee: fee.try_into()?,
+            signer_infos: auth_info
+                .signer_infos
+                .iter()
+                .map(|info| TxSignerInfo {
+                    public_key: info.public_key.clone(),
+                    sign_mode: match info.mode_info.as_ref().and_then(|m| m.sum.as_ref()) {
+                        Some(mode_info::Sum::Single(single)) => SignMode::from_i32(single.mode),
+                        _ => None,
+                    },
+                    sequence: info.sequence,
+                })
+                .collect(),
+            auth_info,
+            signatures: self.0.signatures.clone(),
+            raw: self.0.clone(),
+        })
+    }
+}
+
+/// A decoded transaction, see [`RawTx::decode`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tx {
+    pub msgs: Vec<Any>,
+    pub memo: String,
+    pub timeout_height: u64,
+    pub fee: Fee,
+    pub signer_infos: Vec<TxSignerInfo>,
+    pub auth_info: AuthInfo,
+    pub signatures: Vec<Vec<u8>>,
+    // direct mode signs the exact bytes the tx was encoded with
+    raw: TxRaw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxSignerInfo {
+    pub public_key: Option<Any>,
+    /// `None` for multisig signers
+    pub sign_mode: Option<SignMode>,
+    pub sequence: u64,
+}
+
+impl Tx {
+    /// Rebuilds the sign doc of every signer and checks its signature, like the ante handler does.
+    /// `accounts` supplies the account numbers and sequences of the signers, and the public keys
+    /// of signers whose signer info has none. Multisig signers need at least the threshold of
+    /// member signatures. Fails with [`ChainError::UnknownMsgSigners`] when a msg has no
+    /// registered signers, see [`Tx::verify_signatures_of`] for those,
+    /// and with [`ChainError::InvalidSignatures`] listing every signer that did not verify.
+    pub fn verify_signatures(
+        &self,
+        chain_id: &str,
+        accounts: &[Account],
+    ) -> Result<(), ChainError> {
+        let signers = tx_signers(&self.msgs, &self.fee)?;
+        self.verify_signatures_of(&signers, chain_id, accounts)
+    }
+
+    /// Like [`Tx::verify_signatures`], with the signers the msgs require given in signer info order,
+    /// for txs with msgs the signer registry does not know about
+    pub fn verify_signatures_of(
+        &self,
+        signers: &[Address],
+        chain_id: &str,
+        accounts: &[Account],
+    ) -> Result<(), ChainError> {
+        if signers.len() != self.signer_infos.len() || signers.len() != self.signatures.len() {
+            return Err(ChainError::TxSigners {
+                message: format!(
+                    "expected {} signers, found {} signer infos and {} signatures",
+                    signers.len(),
+                    self.signer_infos.len(),
+                    self.signatures.len()
+                ),
+            });
+        }
+
+        let failures: Vec<_> = signers
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter_map(|(index, address)| {
+                self.verify_signature(index, &address, chain_id, accounts)
+                    .err()
+                    .map(|reason| SignatureFailure {
+                        index,
+                        address,
+                        reason,
+                    })
+            })
+            .collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ChainError::InvalidSignatures { failures })
+        }
+    }
+
+    /// The addresses of the public keys in the signer infos, in signer info order.
+    /// `None` if a signer info has no public key, as when the account already has one on chain.
+    pub(crate) fn signer_info_addresses(&self, prefix: &str) -> Option<Vec<Address>> {
+        self.signer_infos
+            .iter()
+            .map(|info| {
+                let any = info.public_key.as_ref()?;
+                if any.type_url == MULTISIG_TYPE_URL {
+                    return MultisigKey::from_any(any).ok()?.address(prefix).ok();
+                }
+                let algorithm = KeyAlgorithm::from_type_url(&any.type_url)?;
+                let public_key = public_key_from_any(any.clone()).ok()?;
+                algorithm.address(&public_key, prefix).ok()
+            })
+            .collect()
+    }
+
+    fn verify_signature(
+        &self,
+        index: usize,
+        address: &Address,
+        chain_id: &str,
+        accounts: &[Account],
+    ) -> Result<(), SignatureFailureReason> {
+        let info = &self.signer_infos[index];
+        let account = accounts
+            .iter()
+            .find(|a| &a.address == address)
+            .ok_or(SignatureFailureReason::MissingAccount)?;
+
+        match &info.public_key {
+            Some(any) if any.type_url == MULTISIG_TYPE_URL => {
+                self.verify_multisig(index, any, address, chain_id, account)?
+            }
+            _ => self.verify_single(index, address, chain_id, account)?,
+        }
+        if info.sequence != account.sequence {
+            return Err(SignatureFailureReason::SequenceMismatch {
+                expected: account.sequence,
+                got: info.sequence,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn verify_single(
+        &self,
+        index: usize,
+        address: &Address,
+        chain_id: &str,
+        account: &Account,
+    ) -> Result<(), SignatureFailureReason> {
+        let info = &self.signer_infos[index];
+        let (public_key, algorithm) = match &info.public_key {
+            Some(any) => {
+                let public_key = public_key_from_any(any.clone()).map_err(|e| {
+                    SignatureFailureReason::InvalidPublicKey {
+                        message: e.to_string(),
+                    }
+                })?;
+                let algorithm = KeyAlgorithm::from_type_url(&any.type_url).ok_or_else(|| {
+                    SignatureFailureReason::InvalidPublicKey {
+                        message: format!("unsupported public key type {}", any.type_url),
+                    }
+                })?;
+                (public_key, algorithm)
+            }
+            None => {
+                let public_key = account
+                    .pubkey
+                    .ok_or(SignatureFailureReason::MissingPublicKey)?;
+                let algorithm = KeyAlgorithm::from_address(&public_key, address)
+                    .ok_or(SignatureFailureReason::PublicKeyMismatch)?;
+                (public_key, algorithm)
+            }
+        };
+        if algorithm
+            .address(&public_key, address.prefix())
+            .ok()
+            .as_ref()
+            != Some(address)
+        {
+            return Err(SignatureFailureReason::PublicKeyMismatch);
+        }
+
+        let sign_bytes = self.sign_bytes(info.sign_mode, chain_id, account, info.sequence)?;
+        if !algorithm.verify(&public_key, &sign_bytes, &self.signatures[index]) {
+            return Err(SignatureFailureReason::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    // every member that is set in the bit array signs the same sign doc in its own mode,
+    // and their signatures are packed in a `MultiSignature` in member order
+    fn verify_multisig(
+        &self,
+        index: usize,
+        any: &Any,
+        address: &Address,
+        chain_id: &str,
+        account: &Account,
+    ) -> Result<(), SignatureFailureReason> {
+        let multisig =
+            MultisigKey::from_any(any).map_err(|e| SignatureFailureReason::InvalidPublicKey {
+                message: e.to_string(),
+            })?;
+        if multisig.address(address.prefix()).ok().as_ref() != Some(address) {
+            return Err(SignatureFailureReason::PublicKeyMismatch);
+        }
+
+        let multi = match self.auth_info.signer_infos[index]
+            .mode_info
+            .as_ref()
+            .and_then(|m| m.sum.as_ref())
+        {
+            Some(mode_info::Sum::Multi(multi)) => multi,
+            _ => return Err(SignatureFailureReason::InvalidSignature),
+        };
+        let signatures = MultiSignature::decode(self.signatures[index].as_slice())
+            .map_err(|_| SignatureFailureReason::InvalidSignature)?
+            .signatures;
+
+        let bitarray = multi.bitarray.clone().unwrap_or_default();
+        let members: Vec<_> = multisig
+            .public_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                bitarray
+                    .elems
+                    .get(i / 8)
+                    .is_some_and(|elem| elem & (0x80 >> (i % 8)) != 0)
+            })
+            .map(|(_, key)| key)
+            .collect();
+        if bit_array_len(&bitarray) != multisig.public_keys.len()
+            || members.len() != signatures.len()
+            || members.len() != multi.mode_infos.len()
+            || members.len() < multisig.threshold as usize
+        {
+            return Err(SignatureFailureReason::InvalidSignature);
+        }
+
+        let sequence = self.signer_infos[index].sequence;
+        for ((public_key, signature), mode_info) in
+            members.into_iter().zip(&signatures).zip(&multi.mode_infos)
+        {
+            let mode = match &mode_info.sum {
+                Some(mode_info::Sum::Single(single)) => SignMode::from_i32(single.mode),
+                _ => None,
+            };
+            let sign_bytes = self.sign_bytes(mode, chain_id, account, sequence)?;
+            if !KeyAlgorithm::Secp256k1.verify(public_key, &sign_bytes, signature) {
+                return Err(SignatureFailureReason::InvalidSignature);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sign_bytes(
+        &self,
+        sign_mode: Option<SignMode>,
+        chain_id: &str,
+        account: &Account,
+        sequence: u64,
+    ) -> Result<Vec<u8>, SignatureFailureReason> {
+        match sign_mode {
+            Some(SignMode::Direct) => Ok(SignDoc {
+                body_bytes: self.raw.body_bytes.clone(),
+                auth_info_bytes: self.raw.auth_info_bytes.clone(),
+                chain_id: chain_id.to_string(),
+                account_number: account.account_number,
+            }
+            .encode_to_vec()),
+
+            Some(SignMode::LegacyAminoJson) => StdSignDoc::new(
+                &self.msgs,
+                self.timeout_height,
+                &self.memo,
+                account.account_number,
+                sequence,
+                &self.fee,
+                chain_id,
+            )
+            .and_then(|doc| doc.to_sign_bytes())
+            .map_err(|e| SignatureFailureReason::SignDoc {
+                message: e.to_string(),
+            }),
+
+            mode => Err(SignatureFailureReason::UnsupportedSignMode {
+                mode: mode.map_or("multi", |m| m.as_str_name()).to_string(),
+            }),
+        }
+    }
+
+    /// The transaction as it was decoded
+    pub fn to_raw(&self) -> RawTx {
+        RawTx(self.raw.clone())
+    }
+}
+
+/// Number of bits in a `CompactBitArray`, the last byte only holds `extra_bits_stored` of them
+fn bit_array_len(bitarray: &CompactBitArray) -> usize {
+    match bitarray.extra_bits_stored {
+        0 => bitarray.elems.len() * 8,
+        extra => bitarray.elems.len().saturating_sub(1) * 8 + extra as usize,
+    }
 }
 
 impl From<RawTx> for TxRaw {
@@ -43,3 +374,227 @@ impl From<Raw> for RawTx {
         RawTx(tx.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::coin::Coin;
+    use crate::chain::msg::IntoAny;
+    use crate::modules::bank::model::SendRequest;
+    use crate::signing_key::multisig::MultisigTx;
+    use crate::signing_key::signer::{sign_tx, LocalSigner, Signer};
+
+    fn coin() -> Coin {
+        Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_signatures_reports_failed_signer() {
+        let signer = LocalSigner::from_raw(&[5; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let account = Account {
+            address: signer.address("juno").await.unwrap(),
+            pubkey: None,
+            account_number: 9,
+            sequence: 4,
+        };
+        let coin = Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        };
+        let msg = SendRequest {
+            from: account.address.clone(),
+            to: account.address.clone(),
+            amounts: vec![coin.clone()],
+        };
+        let fee = Fee::new(coin, 100_000u64, None, None);
+        let raw = sign_tx(&signer, vec![msg], 0, "", account.clone(), fee, "juno-1")
+            .await
+            .unwrap();
+
+        let tx = RawTx::from_bytes(&raw.to_bytes().unwrap())
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(tx.signer_infos[0].sign_mode, Some(SignMode::Direct));
+        assert_eq!(tx.signer_infos[0].sequence, 4);
+        tx.verify_signatures("juno-1", &[account.clone()]).unwrap();
+
+        let failures =
+            |chain_id: &str, accounts: &[Account]| match tx.verify_signatures(chain_id, accounts) {
+                Err(ChainError::InvalidSignatures { failures }) => failures,
+                res => panic!("unexpected result {res:?}"),
+            };
+        assert_eq!(
+            failures("juno-2", &[account.clone()])[0].reason,
+            SignatureFailureReason::InvalidSignature
+        );
+        assert_eq!(
+            failures("juno-1", &[])[0].reason,
+            SignatureFailureReason::MissingAccount
+        );
+        let moved_on = Account {
+            sequence: 5,
+            ..account
+        };
+        assert_eq!(
+            failures("juno-1", &[moved_on])[0].reason,
+            SignatureFailureReason::SequenceMismatch {
+                expected: 5,
+                got: 4
+            }
+        );
+    }
+
+    // a msg of a module the signer registry does not know about
+    struct Unregistered;
+
+    impl IntoAny for Unregistered {
+        type Err = ChainError;
+
+        fn into_any(self) -> Result<Any, ChainError> {
+            Ok(Any {
+                type_url: "/test.unregistered.v1.MsgDoSomething".to_string(),
+                value: vec![1, 2, 3],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn unregistered_msg_signers_are_not_guessed() {
+        let owner = LocalSigner::from_raw(&[6; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let owner_account = Account {
+            address: owner.address("juno").await.unwrap(),
+            pubkey: None,
+            account_number: 2,
+            sequence: 0,
+        };
+        let stranger = LocalSigner::from_raw(&[7; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let stranger_account = Account {
+            address: stranger.address("juno").await.unwrap(),
+            pubkey: None,
+            account_number: 3,
+            sequence: 0,
+        };
+        let fee = Fee::new(coin(), 100_000u64, None, None);
+        let raw = sign_tx(
+            &stranger,
+            vec![Unregistered],
+            0,
+            "",
+            stranger_account.clone(),
+            fee,
+            "juno-1",
+        )
+        .await
+        .unwrap();
+
+        // the signer info keys are whoever signed, not who the msg needs
+        let tx = raw.decode().unwrap();
+        let accounts = [owner_account.clone(), stranger_account.clone()];
+        assert!(matches!(
+            tx.verify_signatures("juno-1", &accounts),
+            Err(ChainError::UnknownMsgSigners { .. })
+        ));
+
+        match tx.verify_signatures_of(&[owner_account.address.clone()], "juno-1", &accounts) {
+            Err(ChainError::InvalidSignatures { failures }) => {
+                assert_eq!(failures[0].address, owner_account.address);
+                assert_eq!(
+                    failures[0].reason,
+                    SignatureFailureReason::PublicKeyMismatch
+                );
+            }
+            res => panic!("unexpected result {res:?}"),
+        }
+        tx.verify_signatures_of(&[stranger_account.address], "juno-1", &accounts)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_multisig_signatures() {
+        let members: Vec<_> = (1..=3u8)
+            .map(|i| {
+                LocalSigner::from_raw(&[i; 32])
+                    .unwrap()
+                    .with_algorithm(KeyAlgorithm::Secp256k1)
+            })
+            .collect();
+        let mut public_keys = vec![];
+        for member in &members {
+            public_keys.push(member.public_key().await.unwrap());
+        }
+        let multisig = MultisigKey::new(2, public_keys).unwrap();
+        let account = Account {
+            address: multisig.address("juno").unwrap(),
+            pubkey: None,
+            account_number: 11,
+            sequence: 3,
+        };
+        let msg = SendRequest {
+            from: account.address.clone(),
+            to: account.address.clone(),
+            amounts: vec![coin()],
+        };
+        let fee = Fee::new(coin(), 200_000u64, None, None);
+        let multisig_tx =
+            MultisigTx::new(multisig, vec![msg], 0, "", &account, fee, "juno-1").unwrap();
+
+        let first = multisig_tx.sign(&members[0]).await.unwrap();
+        let third = multisig_tx.sign(&members[2]).await.unwrap();
+        let tx = multisig_tx
+            .combine([first.clone(), third])
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(tx.signer_infos[0].sign_mode, None);
+        tx.verify_signatures("juno-1", &[account.clone()]).unwrap();
+
+        let failures = |tx: &Tx| match tx.verify_signatures("juno-1", &[account.clone()]) {
+            Err(ChainError::InvalidSignatures { failures }) => failures[0].reason.clone(),
+            res => panic!("unexpected result {res:?}"),
+        };
+
+        // the second member signed with the wrong chain id, in the slot of the third member
+        let mut wrong_chain = multisig_tx.clone();
+        wrong_chain.chain_id = "juno-2".to_string();
+        let forged = wrong_chain.sign(&members[1]).await.unwrap().signature;
+        let mut raw: TxRaw = tx.to_raw().into();
+        let mut signatures = MultiSignature::decode(raw.signatures[0].as_slice()).unwrap();
+        signatures.signatures[1] = forged;
+        raw.signatures[0] = signatures.encode_to_vec();
+        assert_eq!(
+            failures(&RawTx::from(raw).decode().unwrap()),
+            SignatureFailureReason::InvalidSignature
+        );
+
+        // one member is below the threshold
+        let mut raw: TxRaw = tx.to_raw().into();
+        let mut auth_info = tx.auth_info.clone();
+        if let Some(mode_info::Sum::Multi(multi)) = auth_info.signer_infos[0]
+            .mode_info
+            .as_mut()
+            .and_then(|m| m.sum.as_mut())
+        {
+            multi.bitarray.as_mut().unwrap().elems = vec![0b1000_0000];
+            multi.mode_infos.truncate(1);
+        }
+        raw.auth_info_bytes = auth_info.encode_to_vec();
+        raw.signatures[0] = MultiSignature {
+            signatures: vec![first.signature],
+        }
+        .encode_to_vec();
+        assert_eq!(
+            failures(&RawTx::from(raw).decode().unwrap()),
+            SignatureFailureReason::InvalidSignature
+        );
+    }
+}
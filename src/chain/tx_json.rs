@@ -0,0 +1,853 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cosmrs::proto::cosmos::bank::v1beta1::MsgSend;
+use cosmrs::proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use cosmrs::proto::cosmos::crypto::secp256k1::PubKey as ProtoPubKey;
+use cosmrs::proto::cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward;
+use cosmrs::proto::cosmos::staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate};
+use cosmrs::proto::cosmos::tx::v1beta1::{
+    mode_info, AuthInfo, Fee as ProtoFee, ModeInfo, SignerInfo, Tip, TxBody, TxRaw,
+};
+use cosmrs::proto::cosmwasm::wasm::v1::{
+    MsgExecuteContract, MsgInstantiateContract, MsgMigrateContract,
+};
+use cosmrs::proto::traits::{Message, MessageExt, TypeUrl};
+use cosmrs::tx::SignMode;
+use cosmrs::Any;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::RawValue, Value};
+
+use crate::signing_key::algorithm::KeyAlgorithm;
+
+use super::error::ChainError;
+use super::tx::RawTx;
+
+/// Converts the proto message in an [`Any`] to its proto JSON fields, without `@type`
+pub type JsonEncoder = fn(&Any) -> Result<Value, ChainError>;
+
+/// Converts proto JSON fields back to an [`Any`] of the registered type
+pub type JsonDecoder = fn(&Value) -> Result<Any, ChainError>;
+
+/// Like [`JsonEncoder`], for messages that embed JSON which has to be kept byte for byte
+pub type RawJsonEncoder = fn(&Any) -> Result<Box<RawValue>, ChainError>;
+
+/// Like [`JsonDecoder`], gets the message JSON exactly as it was read
+pub type RawJsonDecoder = fn(&RawValue) -> Result<Any, ChainError>;
+
+#[derive(Clone, Copy, Debug)]
+enum Codec {
+    Value(JsonEncoder, JsonDecoder),
+    Raw(RawJsonEncoder, RawJsonDecoder),
+}
+
+/// Maps proto type urls to their proto JSON encoding, as used by `--generate-only` and `tx sign`
+#[derive(Clone, Debug)]
+pub struct TxJsonRegistry {
+    types: HashMap<String, Codec>,
+}
+
+impl TxJsonRegistry {
+    /// A registry without any messages, see [`TxJsonRegistry::default`] for the built in ones
+    pub fn new() -> Self {
+        Self {
+            types: HashMap::new(),
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        type_url: impl Into<String>,
+        encoder: JsonEncoder,
+        decoder: JsonDecoder,
+    ) {
+        self.types
+            .insert(type_url.into(), Codec::Value(encoder, decoder));
+    }
+
+    /// Registers a message whose JSON is not round tripped through a [`Value`],
+    /// which would sort the keys of embedded JSON and drop its whitespace
+    pub fn register_raw(
+        &mut self,
+        type_url: impl Into<String>,
+        encoder: RawJsonEncoder,
+        decoder: RawJsonDecoder,
+    ) {
+        self.types
+            .insert(type_url.into(), Codec::Raw(encoder, decoder));
+    }
+
+    pub fn to_json(&self, any: &Any) -> Result<MsgJson, ChainError> {
+        let fields = match self.codec(&any.type_url)? {
+            Codec::Value(encoder, _) => to_raw_value(&encoder(any)?)?,
+            Codec::Raw(encoder, _) => encoder(any)?,
+        };
+        with_type_url(&any.type_url, &fields)
+    }
+
+    pub fn from_json(&self, msg: &MsgJson) -> Result<Any, ChainError> {
+        #[derive(Deserialize)]
+        struct Typed {
+            #[serde(rename = "@type")]
+            type_url: String,
+        }
+
+        let Typed { type_url } = serde_json::from_str(msg.0.get())
+            .map_err(|e| ChainError::tx_json(format!("invalid message: {e}")))?;
+        match self.codec(&type_url)? {
+            Codec::Value(_, decoder) => decoder(&fields(&msg.0)?),
+            Codec::Raw(_, decoder) => decoder(&msg.0),
+        }
+    }
+
+    fn codec(&self, type_url: &str) -> Result<Codec, ChainError> {
+        self.types.get(type_url).copied().ok_or_else(|| {
+            ChainError::tx_json(format!("no proto JSON encoding registered for {type_url}"))
+        })
+    }
+}
+
+impl Default for TxJsonRegistry {
+    /// Bank, cosmwasm, staking and distribution messages
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(MsgSend::TYPE_URL, encode_msg_send, decode_msg_send);
+        registry.register_raw(
+            MsgExecuteContract::TYPE_URL,
+            encode_msg_execute_contract,
+            decode_msg_execute_contract,
+        );
+        registry.register_raw(
+            MsgInstantiateContract::TYPE_URL,
+            encode_msg_instantiate_contract,
+            decode_msg_instantiate_contract,
+        );
+        registry.register_raw(
+            MsgMigrateContract::TYPE_URL,
+            encode_msg_migrate_contract,
+            decode_msg_migrate_contract,
+        );
+        registry.register(
+            MsgDelegate::TYPE_URL,
+            encode_msg_delegate,
+            decode_msg_delegate,
+        );
+        registry.register(
+            MsgUndelegate::TYPE_URL,
+            encode_msg_undelegate,
+            decode_msg_undelegate,
+        );
+        registry.register(
+            MsgBeginRedelegate::TYPE_URL,
+            encode_msg_begin_redelegate,
+            decode_msg_begin_redelegate,
+        );
+        registry.register(
+            MsgWithdrawDelegatorReward::TYPE_URL,
+            encode_msg_withdraw_delegator_reward,
+            decode_msg_withdraw_delegator_reward,
+        );
+        registry
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<TxJsonRegistry> = RwLock::new(TxJsonRegistry::default());
+}
+
+/// Registers a message type in the registry used to export and import tx JSON
+pub fn register_json_msg(type_url: impl Into<String>, encoder: JsonEncoder, decoder: JsonDecoder) {
+    REGISTRY
+        .write()
+        .unwrap()
+        .register(type_url, encoder, decoder);
+}
+
+/// Converts `any` with the registry used to export tx JSON
+pub fn msg_to_json(any: &Any) -> Result<MsgJson, ChainError> {
+    REGISTRY.read().unwrap().to_json(any)
+}
+
+/// Converts `msg` with the registry used to import tx JSON
+pub fn msg_from_json(msg: &MsgJson) -> Result<Any, ChainError> {
+    REGISTRY.read().unwrap().from_json(msg)
+}
+
+/// A message of a [`TxJson`], kept as the JSON text it was read as.
+/// Two messages are equal if they are the same JSON, whatever their key order and whitespace.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MsgJson(pub Box<RawValue>);
+
+impl PartialEq for MsgJson {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (fields(&self.0), fields(&other.0)),
+            (Ok(a), Ok(b)) if a == b
+        )
+    }
+}
+
+/// A tx in the JSON printed by `gaiad tx ... --generate-only` and `gaiad tx sign`.
+/// Exported messages list `@type` first and their other keys may come out in a different order
+/// than gaiad prints them, but contract msgs are kept byte for byte.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TxJson {
+    pub body: TxBodyJson,
+    pub auth_info: AuthInfoJson,
+    #[serde(default)]
+    pub signatures: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TxBodyJson {
+    pub messages: Vec<MsgJson>,
+    #[serde(default)]
+    pub memo: String,
+    #[serde(default = "zero")]
+    pub timeout_height: String,
+    #[serde(default)]
+    pub extension_options: Vec<MsgJson>,
+    #[serde(default)]
+    pub non_critical_extension_options: Vec<MsgJson>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AuthInfoJson {
+    #[serde(default)]
+    pub signer_infos: Vec<SignerInfoJson>,
+    pub fee: FeeJson,
+    #[serde(default)]
+    pub tip: Option<TipJson>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SignerInfoJson {
+    pub public_key: Option<Value>,
+    pub mode_info: ModeInfoJson,
+    pub sequence: String,
+}
+
+/// Only single signer mode infos are supported, multisig txs are put together with `MultisigTx`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ModeInfoJson {
+    pub single: SingleModeJson,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SingleModeJson {
+    pub mode: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeeJson {
+    pub amount: Vec<CoinJson>,
+    pub gas_limit: String,
+    #[serde(default)]
+    pub payer: String,
+    #[serde(default)]
+    pub granter: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TipJson {
+    pub amount: Vec<CoinJson>,
+    pub tipper: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CoinJson {
+    pub denom: String,
+    pub amount: String,
+}
+
+impl TxJson {
+    /// Decodes a signed or unsigned tx
+    pub fn from_raw(tx: &RawTx) -> Result<Self, ChainError> {
+        let raw: TxRaw = tx.clone().into();
+        let body =
+            TxBody::decode(raw.body_bytes.as_slice()).map_err(ChainError::prost_proto_decoding)?;
+        let auth_info = AuthInfo::decode(raw.auth_info_bytes.as_slice())
+            .map_err(ChainError::prost_proto_decoding)?;
+        let fee = auth_info.fee.unwrap_or_default();
+
+        Ok(Self {
+            body: TxBodyJson {
+                messages: body
+                    .messages
+                    .iter()
+                    .map(msg_to_json)
+                    .collect::<Result<_, _>>()?,
+                memo: body.memo,
+                timeout_height: body.timeout_height.to_string(),
+                extension_options: body
+                    .extension_options
+                    .iter()
+                    .map(msg_to_json)
+                    .collect::<Result<_, _>>()?,
+                non_critical_extension_options: body
+                    .non_critical_extension_options
+                    .iter()
+                    .map(msg_to_json)
+                    .collect::<Result<_, _>>()?,
+            },
+            auth_info: AuthInfoJson {
+                signer_infos: auth_info
+                    .signer_infos
+                    .into_iter()
+                    .map(SignerInfoJson::from_proto)
+                    .collect::<Result<_, _>>()?,
+                fee: FeeJson {
+                    amount: fee.amount.iter().map(CoinJson::from).collect(),
+                    gas_limit: fee.gas_limit.to_string(),
+                    payer: fee.payer,
+                    granter: fee.granter,
+                },
+                tip: auth_info.tip.map(|tip| TipJson {
+                    amount: tip.amount.iter().map(CoinJson::from).collect(),
+                    tipper: tip.tipper,
+                }),
+            },
+            signatures: raw
+                .signatures
+                .iter()
+                .map(|signature| STANDARD.encode(signature))
+                .collect(),
+        })
+    }
+
+    /// Encodes the tx, ready for `broadcast_tx_*` when it is signed
+    pub fn to_raw(&self) -> Result<RawTx, ChainError> {
+        let body = TxBody {
+            messages: self
+                .body
+                .messages
+                .iter()
+                .map(msg_from_json)
+                .collect::<Result<_, _>>()?,
+            memo: self.body.memo.clone(),
+            timeout_height: parse_u64(&self.body.timeout_height, "timeout_height")?,
+            extension_options: self
+                .body
+                .extension_options
+                .iter()
+                .map(msg_from_json)
+                .collect::<Result<_, _>>()?,
+            non_critical_extension_options: self
+                .body
+                .non_critical_extension_options
+                .iter()
+                .map(msg_from_json)
+                .collect::<Result<_, _>>()?,
+        };
+
+        let fee = &self.auth_info.fee;
+        let auth_info = AuthInfo {
+            signer_infos: self
+                .auth_info
+                .signer_infos
+                .iter()
+                .map(SignerInfoJson::to_proto)
+                .collect::<Result<_, _>>()?,
+            fee: Some(ProtoFee {
+                amount: fee.amount.iter().map(ProtoCoin::from).collect(),
+                gas_limit: parse_u64(&fee.gas_limit, "gas_limit")?,
+                payer: fee.payer.clone(),
+                granter: fee.granter.clone(),
+            }),
+            tip: self.auth_info.tip.as_ref().map(|tip| Tip {
+                amount: tip.amount.iter().map(ProtoCoin::from).collect(),
+                tipper: tip.tipper.clone(),
+            }),
+        };
+
+        Ok(TxRaw {
+            body_bytes: body.to_bytes().map_err(ChainError::prost_proto_encoding)?,
+            auth_info_bytes: auth_info
+                .to_bytes()
+                .map_err(ChainError::prost_proto_encoding)?,
+            signatures: self
+                .signatures
+                .iter()
+                .map(|signature| STANDARD.decode(signature).map_err(ChainError::tx_json))
+                .collect::<Result<_, _>>()?,
+        }
+        .into())
+    }
+}
+
+impl SignerInfoJson {
+    fn from_proto(info: SignerInfo) -> Result<Self, ChainError> {
+        let mode = match info.mode_info.and_then(|m| m.sum) {
+            Some(mode_info::Sum::Single(single)) => SignMode::from_i32(single.mode)
+                .ok_or_else(|| ChainError::tx_json(format!("unknown sign mode {}", single.mode)))?,
+            _ => {
+                return Err(ChainError::tx_json(
+                    "only single signer infos are supported",
+                ))
+            }
+        };
+
+        Ok(Self {
+            public_key: info
+                .public_key
+                .as_ref()
+                .map(public_key_to_json)
+                .transpose()?,
+            mode_info: ModeInfoJson {
+                single: SingleModeJson {
+                    mode: mode.as_str_name().to_string(),
+                },
+            },
+            sequence: info.sequence.to_string(),
+        })
+    }
+
+    fn to_proto(&self) -> Result<SignerInfo, ChainError> {
+        let mode = SignMode::from_str_name(&self.mode_info.single.mode).ok_or_else(|| {
+            ChainError::tx_json(format!("unknown sign mode {}", self.mode_info.single.mode))
+        })?;
+
+        Ok(SignerInfo {
+            public_key: self
+                .public_key
+                .as_ref()
+                .map(public_key_from_json)
+                .transpose()?,
+            mode_info: Some(ModeInfo {
+                sum: Some(mode_info::Sum::Single(mode_info::Single {
+                    mode: mode as i32,
+                })),
+            }),
+            sequence: parse_u64(&self.sequence, "sequence")?,
+        })
+    }
+}
+
+impl From<&ProtoCoin> for CoinJson {
+    fn from(coin: &ProtoCoin) -> Self {
+        Self {
+            denom: coin.denom.clone(),
+            amount: coin.amount.clone(),
+        }
+    }
+}
+
+impl From<&CoinJson> for ProtoCoin {
+    fn from(coin: &CoinJson) -> Self {
+        Self {
+            denom: coin.denom.clone(),
+            amount: coin.amount.clone(),
+        }
+    }
+}
+
+fn zero() -> String {
+    "0".to_string()
+}
+
+fn public_key_to_json(any: &Any) -> Result<Value, ChainError> {
+    if KeyAlgorithm::from_type_url(&any.type_url).is_none() {
+        return Err(ChainError::tx_json(format!(
+            "unsupported public key type {}",
+            any.type_url
+        )));
+    }
+    let key =
+        ProtoPubKey::decode(any.value.as_slice()).map_err(ChainError::prost_proto_decoding)?;
+    Ok(json!({"@type": any.type_url, "key": STANDARD.encode(key.key)}))
+}
+
+fn public_key_from_json(value: &Value) -> Result<Any, ChainError> {
+    let type_url = string(value, "@type")?;
+    if KeyAlgorithm::from_type_url(&type_url).is_none() {
+        return Err(ChainError::tx_json(format!(
+            "unsupported public key type {type_url}"
+        )));
+    }
+    let key = STANDARD
+        .decode(string(value, "key")?)
+        .map_err(ChainError::tx_json)?;
+    Ok(Any {
+        type_url,
+        value: ProtoPubKey { key }.encode_to_vec(),
+    })
+}
+
+fn to_raw_value(value: &Value) -> Result<Box<RawValue>, ChainError> {
+    serde_json::value::to_raw_value(value).map_err(ChainError::tx_json)
+}
+
+fn fields(msg: &RawValue) -> Result<Value, ChainError> {
+    serde_json::from_str(msg.get()).map_err(ChainError::tx_json)
+}
+
+/// Puts `@type` in front of the other fields of a message, leaving them as they are
+fn with_type_url(type_url: &str, fields: &RawValue) -> Result<MsgJson, ChainError> {
+    let rest = fields
+        .get()
+        .strip_prefix('{')
+        .ok_or_else(|| ChainError::tx_json(format!("{type_url} is not a JSON object")))?;
+    let separator = if rest.trim_start().starts_with('}') {
+        ""
+    } else {
+        ","
+    };
+    let type_url = serde_json::to_string(type_url).map_err(ChainError::tx_json)?;
+    RawValue::from_string(format!("{{\"@type\":{type_url}{separator}{rest}"))
+        .map(MsgJson)
+        .map_err(ChainError::tx_json)
+}
+
+fn parse_u64(value: &str, name: &str) -> Result<u64, ChainError> {
+    value
+        .parse()
+        .map_err(|e| ChainError::tx_json(format!("invalid {name} {value:?}: {e}")))
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, ChainError> {
+    value
+        .get(name)
+        .ok_or_else(|| ChainError::tx_json(format!("missing field {name}")))
+}
+
+fn string(value: &Value, name: &str) -> Result<String, ChainError> {
+    field(value, name)?
+        .as_str()
+        .map(ToString::to_string)
+        .ok_or_else(|| ChainError::tx_json(format!("field {name} is not a string")))
+}
+
+// 64 bit integers are strings in proto JSON, but plain numbers are accepted too
+fn uint(value: &Value, name: &str) -> Result<u64, ChainError> {
+    match field(value, name)? {
+        Value::String(s) => parse_u64(s, name),
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| ChainError::tx_json(format!("invalid {name} {n}"))),
+        _ => Err(ChainError::tx_json(format!(
+            "field {name} is not an integer"
+        ))),
+    }
+}
+
+fn decode<M: Message + Default>(any: &Any) -> Result<M, ChainError> {
+    M::decode(any.value.as_slice()).map_err(ChainError::prost_proto_decoding)
+}
+
+fn encode<M: MessageExt + TypeUrl>(msg: M) -> Result<Any, ChainError> {
+    msg.to_any().map_err(ChainError::prost_proto_encoding)
+}
+
+fn coin(coin: &ProtoCoin) -> Value {
+    json!({"denom": coin.denom, "amount": coin.amount})
+}
+
+fn coins(coins: &[ProtoCoin]) -> Value {
+    Value::Array(coins.iter().map(coin).collect())
+}
+
+fn coin_from(value: &Value) -> Result<ProtoCoin, ChainError> {
+    Ok(ProtoCoin {
+        denom: string(value, "denom")?,
+        amount: string(value, "amount")?,
+    })
+}
+
+fn coins_from(value: &Value, name: &str) -> Result<Vec<ProtoCoin>, ChainError> {
+    field(value, name)?
+        .as_array()
+        .ok_or_else(|| ChainError::tx_json(format!("field {name} is not an array")))?
+        .iter()
+        .map(coin_from)
+        .collect()
+}
+
+fn optional_coin_from(value: &Value, name: &str) -> Result<Option<ProtoCoin>, ChainError> {
+    match value.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(coin) => coin_from(coin).map(Some),
+    }
+}
+
+// wasmd prints contract msgs inline instead of as base64 bytes. They are signed as they are,
+// so they never go through a `Value`, which would sort their keys and drop their whitespace.
+fn with_contract_msg(fields: Value, bytes: &[u8]) -> Result<Box<RawValue>, ChainError> {
+    #[derive(Serialize)]
+    struct WithMsg<'a> {
+        #[serde(flatten)]
+        fields: Value,
+        msg: &'a RawValue,
+    }
+
+    let msg: &RawValue = serde_json::from_slice(bytes)
+        .map_err(|e| ChainError::tx_json(format!("contract msg is not valid JSON: {e}")))?;
+    // whitespace around the msg can't be kept in the tx JSON
+    if msg.get().len() != bytes.len() {
+        return Err(ChainError::tx_json(
+            "contract msg starts or ends with whitespace",
+        ));
+    }
+
+    let json = serde_json::to_string(&WithMsg { fields, msg }).map_err(ChainError::tx_json)?;
+    RawValue::from_string(json).map_err(ChainError::tx_json)
+}
+
+fn contract_msg_from(msg: &RawValue) -> Result<Vec<u8>, ChainError> {
+    #[derive(Deserialize)]
+    struct ContractMsg<'a> {
+        #[serde(borrow)]
+        msg: &'a RawValue,
+    }
+
+    let ContractMsg { msg } = serde_json::from_str(msg.get())
+        .map_err(|e| ChainError::tx_json(format!("invalid contract msg: {e}")))?;
+    Ok(msg.get().as_bytes().to_vec())
+}
+
+fn encode_msg_send(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgSend = decode(any)?;
+    Ok(json!({
+        "from_address": msg.from_address,
+        "to_address": msg.to_address,
+        "amount": coins(&msg.amount),
+    }))
+}
+
+fn decode_msg_send(value: &Value) -> Result<Any, ChainError> {
+    encode(MsgSend {
+        from_address: string(value, "from_address")?,
+        to_address: string(value, "to_address")?,
+        amount: coins_from(value, "amount")?,
+    })
+}
+
+fn encode_msg_execute_contract(any: &Any) -> Result<Box<RawValue>, ChainError> {
+    let msg: MsgExecuteContract = decode(any)?;
+    with_contract_msg(
+        json!({
+            "sender": msg.sender,
+            "contract": msg.contract,
+            "funds": coins(&msg.funds),
+        }),
+        &msg.msg,
+    )
+}
+
+fn decode_msg_execute_contract(msg: &RawValue) -> Result<Any, ChainError> {
+    let value = &fields(msg)?;
+    encode(MsgExecuteContract {
+        sender: string(value, "sender")?,
+        contract: string(value, "contract")?,
+        msg: contract_msg_from(msg)?,
+        funds: coins_from(value, "funds")?,
+    })
+}
+
+fn encode_msg_instantiate_contract(any: &Any) -> Result<Box<RawValue>, ChainError> {
+    let msg: MsgInstantiateContract = decode(any)?;
+    with_contract_msg(
+        json!({
+            "sender": msg.sender,
+            "admin": msg.admin,
+            "code_id": msg.code_id.to_string(),
+            "label": msg.label,
+            "funds": coins(&msg.funds),
+        }),
+        &msg.msg,
+    )
+}
+
+fn decode_msg_instantiate_contract(msg: &RawValue) -> Result<Any, ChainError> {
+    let value = &fields(msg)?;
+    encode(MsgInstantiateContract {
+        sender: string(value, "sender")?,
+        admin: value
+            .get("admin")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        code_id: uint(value, "code_id")?,
+        label: string(value, "label")?,
+        msg: contract_msg_from(msg)?,
+        funds: coins_from(value, "funds")?,
+    })
+}
+
+fn encode_msg_migrate_contract(any: &Any) -> Result<Box<RawValue>, ChainError> {
+    let msg: MsgMigrateContract = decode(any)?;
+    with_contract_msg(
+        json!({
+            "sender": msg.sender,
+            "contract": msg.contract,
+            "code_id": msg.code_id.to_string(),
+        }),
+        &msg.msg,
+    )
+}
+
+fn decode_msg_migrate_contract(msg: &RawValue) -> Result<Any, ChainError> {
+    let value = &fields(msg)?;
+    encode(MsgMigrateContract {
+        sender: string(value, "sender")?,
+        contract: string(value, "contract")?,
+        code_id: uint(value, "code_id")?,
+        msg: contract_msg_from(msg)?,
+    })
+}
+
+fn encode_msg_delegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgDelegate = decode(any)?;
+    Ok(json!({
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+        "amount": msg.amount.as_ref().map(coin),
+    }))
+}
+
+fn decode_msg_delegate(value: &Value) -> Result<Any, ChainError> {
+    encode(MsgDelegate {
+        delegator_address: string(value, "delegator_address")?,
+        validator_address: string(value, "validator_address")?,
+        amount: optional_coin_from(value, "amount")?,
+    })
+}
+
+fn encode_msg_undelegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgUndelegate = decode(any)?;
+    Ok(json!({
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+        "amount": msg.amount.as_ref().map(coin),
+    }))
+}
+
+fn decode_msg_undelegate(value: &Value) -> Result<Any, ChainError> {
+    encode(MsgUndelegate {
+        delegator_address: string(value, "delegator_address")?,
+        validator_address: string(value, "validator_address")?,
+        amount: optional_coin_from(value, "amount")?,
+    })
+}
+
+fn encode_msg_begin_redelegate(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgBeginRedelegate = decode(any)?;
+    Ok(json!({
+        "delegator_address": msg.delegator_address,
+        "validator_src_address": msg.validator_src_address,
+        "validator_dst_address": msg.validator_dst_address,
+        "amount": msg.amount.as_ref().map(coin),
+    }))
+}
+
+fn decode_msg_begin_redelegate(value: &Value) -> Result<Any, ChainError> {
+    encode(MsgBeginRedelegate {
+        delegator_address: string(value, "delegator_address")?,
+        validator_src_address: string(value, "validator_src_address")?,
+        validator_dst_address: string(value, "validator_dst_address")?,
+        amount: optional_coin_from(value, "amount")?,
+    })
+}
+
+fn encode_msg_withdraw_delegator_reward(any: &Any) -> Result<Value, ChainError> {
+    let msg: MsgWithdrawDelegatorReward = decode(any)?;
+    Ok(json!({
+        "delegator_address": msg.delegator_address,
+        "validator_address": msg.validator_address,
+    }))
+}
+
+fn decode_msg_withdraw_delegator_reward(value: &Value) -> Result<Any, ChainError> {
+    encode(MsgWithdrawDelegatorReward {
+        delegator_address: string(value, "delegator_address")?,
+        validator_address: string(value, "validator_address")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: &str = "cosmos1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu";
+    const TO: &str = "cosmos1qgpqyqszqgpqyqszqgpqyqszqgpqyqszrh8mx2";
+
+    // output of `gaiad tx bank send --generate-only` of the cosmos-sdk v0.47
+    fn generate_only() -> String {
+        format!(
+            r#"{{"body":{{"messages":[{{"@type":"/cosmos.bank.v1beta1.MsgSend","from_address":"{FROM}","to_address":"{TO}","amount":[{{"denom":"uatom","amount":"1000"}}]}}],"memo":"memo","timeout_height":"0","extension_options":[],"non_critical_extension_options":[]}},"auth_info":{{"signer_infos":[],"fee":{{"amount":[{{"denom":"uatom","amount":"5000"}}],"gas_limit":"200000","payer":"","granter":""}},"tip":null}},"signatures":[]}}"#
+        )
+    }
+
+    #[test]
+    fn generate_only_round_trip() {
+        let json: TxJson = serde_json::from_str(&generate_only()).unwrap();
+        let raw: TxRaw = json.to_raw().unwrap().into();
+
+        let body = TxBody::decode(raw.body_bytes.as_slice()).unwrap();
+        let msg = MsgSend::decode(body.messages[0].value.as_slice()).unwrap();
+        assert_eq!(msg.from_address, FROM);
+        assert_eq!(msg.amount[0].amount, "1000");
+        assert_eq!(body.memo, "memo");
+
+        let exported = TxJson::from_raw(&raw.into()).unwrap();
+        assert_eq!(exported, json);
+        assert_eq!(
+            serde_json::to_value(&exported).unwrap(),
+            serde_json::from_str::<Value>(&generate_only()).unwrap()
+        );
+    }
+
+    #[test]
+    fn contract_msgs_are_inline_json() {
+        let msg = MsgExecuteContract {
+            sender: FROM.to_string(),
+            contract: TO.to_string(),
+            msg: br#"{"increment":{}}"#.to_vec(),
+            funds: vec![],
+        }
+        .to_any()
+        .unwrap();
+
+        let json = msg_to_json(&msg).unwrap();
+        assert_eq!(fields(&json.0).unwrap()["msg"], json!({"increment": {}}));
+        assert_eq!(msg_from_json(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn contract_msgs_keep_their_bytes() {
+        for contract_msg in [r#"{"b":1,"a":2}"#, r#"{"b": 1,  "a": [2, 1.0]}"#] {
+            let msg = MsgExecuteContract {
+                sender: FROM.to_string(),
+                contract: TO.to_string(),
+                msg: contract_msg.as_bytes().to_vec(),
+                funds: vec![],
+            }
+            .to_any()
+            .unwrap();
+            let raw: RawTx = TxRaw {
+                body_bytes: TxBody {
+                    messages: vec![msg],
+                    ..Default::default()
+                }
+                .to_bytes()
+                .unwrap(),
+                auth_info_bytes: AuthInfo::default().to_bytes().unwrap(),
+                signatures: vec![],
+            }
+            .into();
+
+            let json = TxJson::from_raw(&raw).unwrap();
+            let round_trip: TxRaw = json.to_raw().unwrap().into();
+            assert_eq!(round_trip.body_bytes, TxRaw::from(raw.clone()).body_bytes);
+
+            // and through the JSON text
+            let text = serde_json::to_string(&json).unwrap();
+            assert!(text.contains(&format!(r#""msg":{contract_msg}"#)));
+            let imported: TxJson = serde_json::from_str(&text).unwrap();
+            let round_trip: TxRaw = imported.to_raw().unwrap().into();
+            assert_eq!(round_trip.body_bytes, TxRaw::from(raw).body_bytes);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use cosmrs::crypto::PublicKey;
+use tendermint_rpc::endpoint::{
+    abci_query::AbciQuery,
+    broadcast::{tx_async, tx_commit, tx_sync},
+};
+
+use crate::chain::error::ChainError;
+use crate::chain::fee::GasInfo;
+use crate::chain::signers::tx_signers;
+use crate::chain::tx::{RawTx, Tx};
+use crate::modules::auth::error::AccountError;
+use crate::modules::auth::model::{Account, AccountResponse, Address};
+
+use super::client::{ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync};
+use super::sequence::expected_sequence;
+
+/// What an [`AccountCache`] knows about an account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedAccount {
+    pub address: Address,
+    pub account_number: u64,
+    pub pubkey: Option<PublicKey>,
+    /// `None` when it has to be queried again, e.g. after a failed broadcast
+    pub sequence: Option<u64>,
+}
+
+/// Account numbers, public keys and sequences by address.
+///
+/// Account numbers and public keys never change once set, so they are kept forever.
+/// Sequences are bumped locally after successful broadcasts and queried again after failed ones.
+/// Clones share the same cache.
+#[derive(Clone, Debug, Default)]
+pub struct AccountCache {
+    // keyed by address bytes, so the cache does not depend on the bech32 prefix
+    accounts: Arc<Mutex<HashMap<Vec<u8>, CachedAccount>>>,
+}
+
+impl AccountCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, address: &Address) -> Option<CachedAccount> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .get(&address.to_bytes())
+            .cloned()
+    }
+
+    /// The account of `address` if its sequence is known
+    pub fn account(&self, address: &Address) -> Option<Account> {
+        let cached = self.get(address)?;
+        Some(Account {
+            address: address.clone(),
+            pubkey: cached.pubkey,
+            account_number: cached.account_number,
+            sequence: cached.sequence?,
+        })
+    }
+
+    /// Caches a freshly queried account
+    pub fn update(&self, account: &Account) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let cached = accounts
+            .entry(account.address.to_bytes())
+            .or_insert_with(|| CachedAccount {
+                address: account.address.clone(),
+                account_number: account.account_number,
+                pubkey: None,
+                sequence: None,
+            });
+        cached.pubkey = cached.pubkey.or(account.pubkey);
+        cached.sequence = Some(account.sequence);
+    }
+
+    pub fn set_sequence(&self, address: &Address, sequence: u64) {
+        self.with_account(&address.to_bytes(), |cached| {
+            cached.sequence = Some(sequence)
+        });
+    }
+
+    /// Makes the next [`CachedAccountClient::auth_query_account`] of `address` query the chain
+    pub fn forget_sequence(&self, address: &Address) {
+        self.with_account(&address.to_bytes(), |cached| cached.sequence = None);
+    }
+
+    pub fn remove(&self, address: &Address) {
+        self.accounts.lock().unwrap().remove(&address.to_bytes());
+    }
+
+    fn with_account(&self, key: &[u8], f: impl FnOnce(&mut CachedAccount)) {
+        if let Some(cached) = self.accounts.lock().unwrap().get_mut(key) {
+            f(cached);
+        }
+    }
+
+    /// Updates the sequences of the signers of a simulated or broadcasted tx
+    fn after_tx<R>(&self, raw_tx: &RawTx, res: &Result<R, ChainError>, simulated: bool) {
+        let Ok(tx) = raw_tx.decode() else {
+            return;
+        };
+
+        for (key, info) in signer_keys(&tx).into_iter().zip(&tx.signer_infos) {
+            self.with_account(&key, |cached| match res {
+                Ok(_) if simulated => {}
+                // txs of one signer may complete out of order
+                Ok(_) => cached.sequence = cached.sequence.max(Some(info.sequence + 1)),
+                Err(e) => cached.sequence = expected_sequence(e),
+            });
+        }
+    }
+}
+
+// the address bytes of the signers in signer info order, falling back to the
+// public keys in the signer infos for msgs whose signers are not registered
+fn signer_keys(tx: &Tx) -> Vec<Vec<u8>> {
+    if let Ok(signers) = tx_signers(&tx.msgs, &tx.fee) {
+        return signers.iter().map(Address::to_bytes).collect();
+    }
+
+    // the prefix does not change the address bytes
+    tx.signer_info_addresses("cosmos")
+        .unwrap_or_default()
+        .iter()
+        .map(Address::to_bytes)
+        .collect()
+}
+
+/// Wraps a client so `tx_sign`, and every module method built on it,
+/// takes accounts from an [`AccountCache`] instead of querying them for every tx.
+///
+/// Each key must have a single writer: concurrent `tx_sign` calls of one key read the same
+/// cached sequence, so all but one of their txs fail with a sequence mismatch. Concurrent txs
+/// of one key go through a [`SequenceManager`](super::sequence::SequenceManager) on top of this
+/// client instead, which reserves a sequence for every tx. Different keys can sign concurrently.
+#[derive(Debug)]
+pub struct CachedAccountClient<C> {
+    inner: C,
+    cache: AccountCache,
+}
+
+impl<C> CachedAccountClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self::with_cache(inner, AccountCache::new())
+    }
+
+    /// Shares `cache` with other clients, e.g. one per node of the same chain
+    pub fn with_cache(inner: C, cache: AccountCache) -> Self {
+        Self { inner, cache }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn cache(&self) -> &AccountCache {
+        &self.cache
+    }
+}
+
+#[async_trait]
+impl<C> ClientAbciQuery for CachedAccountClient<C>
+where
+    C: ClientAbciQuery<Response = AbciQuery> + Send + Sync,
+{
+    type Response = AbciQuery;
+
+    async fn abci_query<V>(
+        &self,
+        path: Option<String>,
+        data: V,
+        height: Option<u32>,
+        prove: bool,
+    ) -> Result<Self::Response, ChainError>
+    where
+        V: Into<Vec<u8>> + Send,
+    {
+        ClientAbciQuery::abci_query(&self.inner, path, data, height, prove).await
+    }
+
+    async fn auth_query_account(&self, address: Address) -> Result<AccountResponse, AccountError> {
+        if let Some(account) = self.cache.account(&address) {
+            return Ok(AccountResponse { account });
+        }
+
+        let res = ClientAbciQuery::auth_query_account(&self.inner, address).await?;
+        self.cache.update(&res.account);
+        Ok(res)
+    }
+
+    async fn query_simulate_tx(&self, tx: &RawTx) -> Result<GasInfo, ChainError> {
+        let res = ClientAbciQuery::query_simulate_tx(&self.inner, tx).await;
+        self.cache.after_tx(tx, &res, true);
+        res
+    }
+}
+
+#[async_trait]
+impl<C> ClientTxCommit for CachedAccountClient<C>
+where
+    C: ClientTxCommit<Response = tx_commit::Response> + Send + Sync,
+{
+    type Response = tx_commit::Response;
+
+    async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let res = ClientTxCommit::broadcast_tx_commit(&self.inner, raw_tx).await;
+        self.cache.after_tx(raw_tx, &res, false);
+        res
+    }
+}
+
+#[async_trait]
+impl<C> ClientTxSync for CachedAccountClient<C>
+where
+    C: ClientTxSync<Response = tx_sync::Response> + Send + Sync,
+{
+    type Response = tx_sync::Response;
+
+    async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let res = ClientTxSync::broadcast_tx_sync(&self.inner, raw_tx).await;
+        self.cache.after_tx(raw_tx, &res, false);
+        res
+    }
+}
+
+/// Async broadcasts do not wait for `CheckTx`, so the sequence is bumped even if the tx is
+/// rejected later. The next tx then fails with a sequence mismatch, which resyncs the cache.
+#[async_trait]
+impl<C> ClientTxAsync for CachedAccountClient<C>
+where
+    C: ClientTxAsync<Response = tx_async::Response> + Send + Sync,
+{
+    type Response = tx_async::Response;
+
+    async fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let res = ClientTxAsync::broadcast_tx_async(&self.inner, raw_tx).await;
+        self.cache.after_tx(raw_tx, &res, false);
+        res
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use futures::future::join_all;
+
+    use super::*;
+    use crate::chain::request::TxOptions;
+    use crate::clients::fake::FakeChain;
+    use crate::clients::sequence::SequenceManager;
+    use crate::modules::bank::api::BankTxCommit;
+    use crate::signing_key::signer::ToSigner;
+
+    #[tokio::test]
+    async fn skips_account_queries() {
+        let cfg = FakeChain::chain_cfg();
+        let client = CachedAccountClient::new(FakeChain::new(&cfg.chain_id));
+        let chain = client.inner();
+        let (key, sender, req) = chain.funded_sender().await;
+
+        for _ in 0..2 {
+            client
+                .bank_send_commit(&cfg, req.clone(), &key, &TxOptions::default())
+                .await
+                .unwrap();
+        }
+        let cached = client.cache().get(&sender).unwrap();
+        assert_eq!(cached.sequence, Some(2));
+        assert_eq!(
+            cached.account_number,
+            chain.account(&sender).unwrap().account_number
+        );
+
+        // a tx sent around the cache makes the next simulation fail, which resyncs the sequence
+        chain
+            .bank_send_commit(&cfg, req.clone(), &key, &TxOptions::default())
+            .await
+            .unwrap();
+        assert!(client
+            .bank_send_commit(&cfg, req.clone(), &key, &TxOptions::default())
+            .await
+            .is_err());
+        assert_eq!(client.cache().get(&sender).unwrap().sequence, Some(3));
+
+        client
+            .bank_send_commit(&cfg, req, &key, &TxOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(chain.account(&sender).unwrap().sequence, 4);
+    }
+
+    #[tokio::test]
+    async fn concurrent_senders() {
+        let cfg = FakeChain::chain_cfg();
+        let client = CachedAccountClient::new(FakeChain::new(&cfg.chain_id));
+        let chain = client.inner();
+        let (mut keys, mut reqs) = (vec![], vec![]);
+        for _ in 0..3 {
+            let (key, _, req) = chain.funded_sender().await;
+            keys.push(key);
+            reqs.push(req);
+        }
+
+        // different keys do not share a sequence
+        let results = join_all(keys.iter().zip(&reqs).map(|(key, req)| {
+            client.bank_send_commit(&cfg, req.clone(), key, &TxOptions::default())
+        }))
+        .await;
+        assert!(results.iter().all(Result::is_ok));
+
+        // txs of one key take their sequences from a manager
+        let sequences = SequenceManager::new();
+        let results = join_all((0..3).map(|_| {
+            sequences.send_commit(
+                &client,
+                &cfg,
+                vec![reqs[0].clone()],
+                &keys[0],
+                &TxOptions::default(),
+            )
+        }))
+        .await;
+        assert!(results.iter().all(Result::is_ok));
+
+        assert_eq!(chain.account(&reqs[0].from).unwrap().sequence, 4);
+        assert_eq!(client.cache().get(&reqs[0].from).unwrap().sequence, Some(4));
+        assert_eq!(chain.account(&reqs[1].from).unwrap().sequence, 1);
+    }
+}
@@ -4,24 +4,23 @@ use crate::chain::fee::{Fee, GasInfo};
 use crate::chain::msg::IntoAny;
 use crate::chain::request::TxOptions;
 use crate::chain::tx::RawTx;
+use crate::clients::tx_builder::TxBuilder;
 use crate::config::cfg::ChainConfig;
 use crate::modules::auth::error::AccountError;
 use crate::modules::auth::model::{Account, AccountResponse, Address};
 use crate::proto::cosmos::auth::v1beta1::{QueryAccountRequest, QueryAccountResponse};
-use crate::signing_key::key::UserKey;
+use crate::signing_key::signer::{sign_tx_with_mode, simulation_tx, Signer, ToSigner};
 use async_trait::async_trait;
-use cosmrs::proto::cosmos::tx::v1beta1::{SimulateRequest, SimulateResponse, TxRaw};
+use cosmrs::proto::cosmos::tx::v1beta1::{SimulateRequest, SimulateResponse};
 use cosmrs::proto::traits::Message;
 use cosmrs::tendermint::Hash;
 use cosmrs::Any;
 use schemars::JsonSchema;
 
 use cosmrs::tendermint::abci::{Event, EventAttribute};
-use cosmrs::tx::{Body, SignerInfo};
-#[cfg(feature = "mockall")]
-use mockall::automock;
 
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tendermint_rpc::endpoint::tx;
 
 fn encode_msg<T: Message>(msg: T) -> Result<Vec<u8>, ChainError> {
@@ -57,27 +56,94 @@ pub trait GetEvents {
     }
 }
 
-#[cfg_attr(feature = "mockall", automock)]
 #[async_trait]
 pub trait HashSearch: ClientAbciQuery {
-    async fn hash_search(&self, hash: &Hash) -> Result<tx::Response, ChainError>;
+    /// [`HashSearch::hash_search_with`] the default [`WaitOptions`]
+    async fn hash_search(&self, hash: &Hash) -> Result<tx::Response, ChainError> {
+        self.hash_search_with(hash, &WaitOptions::default()).await
+    }
+
+    async fn hash_search_with(
+        &self,
+        hash: &Hash,
+        opts: &WaitOptions,
+    ) -> Result<tx::Response, ChainError>;
+}
+
+/// Controls how long to wait for a broadcasted tx to be included in a block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaitOptions {
+    /// Give up once this much wall-clock time has passed
+    pub timeout: Duration,
+
+    /// The `timeout_height` of the tx. Once the chain is past this height without having
+    /// included the tx, waiting stops with [`ChainError::TxTimeoutHeightExceeded`],
+    /// and the tx can safely be re-submitted. The tx is searched for once more a block later,
+    /// in case the tx indexer lags behind the chain.
+    pub max_height: Option<u64>,
+
+    /// How often to poll `tx_search`, and to check the chain height against `max_height`
+    pub poll_interval: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_height: None,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// A tx that has been included in a block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxInclusion {
+    pub hash: Hash,
+    pub height: u64,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    pub events: Vec<Event>,
+}
+
+impl GetEvents for TxInclusion {
+    fn get_events(&self) -> &[Event] {
+        self.events.as_slice()
+    }
+}
+
+impl From<tx::Response> for TxInclusion {
+    fn from(res: tx::Response) -> Self {
+        Self {
+            hash: res.hash,
+            height: res.height.value(),
+            gas_wanted: res.tx_result.gas_wanted as u64,
+            gas_used: res.tx_result.gas_used as u64,
+            events: res.tx_result.events,
+        }
+    }
+}
+
+#[async_trait]
+pub trait WaitForTx {
+    /// Waits until the tx with `hash` is included in a block.
+    /// Websocket clients subscribe to the tx event, other clients fall back to polling `tx_search`.
+    async fn wait_for_tx(&self, hash: &Hash, opts: &WaitOptions)
+        -> Result<TxInclusion, ChainError>;
 }
 
-#[cfg_attr(feature = "mockall", automock)]
 #[async_trait]
 pub trait ClientTxCommit {
     type Response: GetErr + GetEvents;
     async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError>;
 }
 
-#[cfg_attr(feature = "mockall", automock)]
 #[async_trait]
 pub trait ClientTxSync {
     type Response: GetErr;
     async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError>;
 }
 
-#[cfg_attr(feature = "mockall", automock)]
 #[async_trait]
 pub trait ClientTxAsync {
     type Response: GetErr;
@@ -109,7 +175,22 @@ impl<R, O> QueryResponse<R, O> {
     }
 }
 
-#[cfg_attr(feature = "mockall", automock)]
+pub(crate) fn gas_to_fee(
+    gas_info: &GasInfo,
+    denom: Denom,
+    gas_price: f64,
+    gas_adjustment: f64,
+) -> Fee {
+    // TODO: clean up this gas conversion code to be clearer
+    let gas_limit = (gas_info.gas_used.value() as f64 * gas_adjustment).ceil();
+    let amount = Coin {
+        denom,
+        amount: ((gas_limit * gas_price).ceil() as u64).into(),
+    };
+
+    Fee::new(amount, gas_limit as u64, None, None)
+}
+
 #[async_trait]
 pub trait ClientAbciQuery: Sized {
     type Response: GetErr + GetValue;
@@ -216,22 +297,22 @@ pub trait ClientAbciQuery: Sized {
         Ok(gas_info.into())
     }
 
-    // Sends tx with an empty public_key / signature, like they do in the cosmos-sdk:
-    // https://github.com/cosmos/cosmos-sdk/blob/main/client/tx/tx.go#L133
+    /// Simulates `msgs` as [`ClientAbciQuery::tx_sign`] would sign them, with the same memo,
+    /// timeout height and public key, so the simulated tx is as large as the signed one.
+    /// Only the signature is left empty, like the cosmos-sdk does:
+    /// https://github.com/cosmos/cosmos-sdk/blob/main/client/tx/tx.go#L133
     async fn tx_simulate<I>(
         &self,
-        denom: &str,
-        gas_price: f64,
-        gas_adjustment: f64,
+        chain_cfg: &ChainConfig,
         msgs: I,
+        signer: &dyn Signer,
         account: &Account,
+        tx_options: &TxOptions,
     ) -> Result<Fee, ChainError>
     where
         I: IntoIterator<Item = Any> + Send,
     {
-        let tx = Body::new(msgs, "cosm-client memo", 0u16);
-
-        let denom: Denom = denom.parse()?;
+        let denom: Denom = chain_cfg.denom.parse()?;
 
         let fee = Fee::new(
             Coin {
@@ -243,43 +324,40 @@ pub trait ClientAbciQuery: Sized {
             None,
         );
 
-        let auth_info =
-            SignerInfo::single_direct(None, account.sequence).auth_info(fee.try_into()?);
-
-        let tx_raw = TxRaw {
-            body_bytes: tx.into_bytes().map_err(ChainError::proto_encoding)?,
-            auth_info_bytes: auth_info.into_bytes().map_err(ChainError::proto_encoding)?,
-            signatures: vec![vec![]],
-        };
-
-        let gas_info = self.query_simulate_tx(&tx_raw.into()).await?;
-
-        // TODO: clean up this gas conversion code to be clearer
-        let gas_limit = (gas_info.gas_used.value() as f64 * gas_adjustment).ceil();
-        let amount = Coin {
+        let tx_raw = simulation_tx(
+            signer,
+            tx_options.sign_mode,
+            &msgs.into_iter().collect::<Vec<_>>(),
+            tx_options.timeout_height.unwrap_or_default(),
+            &tx_options.memo,
+            account,
+            &fee,
+        )
+        .await?;
+
+        let gas_info = self.query_simulate_tx(&tx_raw).await?;
+
+        Ok(gas_to_fee(
+            &gas_info,
             denom,
-            amount: ((gas_limit * gas_price).ceil() as u64).into(),
-        };
-
-        let fee = Fee::new(amount, gas_limit as u64, None, None);
-
-        Ok(fee)
+            chain_cfg.gas_price,
+            chain_cfg.gas_adjustment,
+        ))
     }
 
     async fn tx_sign<T>(
         &self,
         chain_cfg: &ChainConfig,
         msgs: Vec<T>,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<RawTx, AccountError>
     where
         T: IntoAny + Clone + Send + Sync,
         <T as IntoAny>::Err: Send + Sync,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let signer = key.to_chain_signer(chain_cfg)?;
+        let sender_addr = signer.address(&chain_cfg.prefix).await?;
 
         let timeout_height = tx_options.timeout_height.unwrap_or_default();
 
@@ -293,31 +371,118 @@ pub trait ClientAbciQuery: Sized {
             fee.clone()
         } else {
             self.tx_simulate(
-                &chain_cfg.denom,
-                chain_cfg.gas_price,
-                chain_cfg.gas_adjustment,
+                chain_cfg,
                 msgs.iter()
                     .map(|m| m.clone().into_any())
                     .collect::<Result<Vec<_>, _>>()
                     .map_err(|e| ChainError::ProtoEncoding {
                         message: e.to_string(),
                     })?,
+                signer.as_ref(),
                 &account,
+                tx_options,
             )
             .await?
         };
 
-        let raw = key
-            .sign(
-                msgs,
-                timeout_height,
-                &tx_options.memo,
-                account,
-                fee,
-                &chain_cfg.chain_id,
-                &chain_cfg.derivation_path,
-            )
-            .await?;
+        let raw = sign_tx_with_mode(
+            signer.as_ref(),
+            tx_options.sign_mode,
+            msgs,
+            timeout_height,
+            &tx_options.memo,
+            account,
+            fee,
+            &chain_cfg.chain_id,
+        )
+        .await?;
         Ok(raw)
     }
+
+    /// Signs msgs that need the signatures of several `keys`, e.g. a fee payer and a sender.
+    /// The accounts of all keys are queried, `tx_options.account` is ignored.
+    /// Msgs of different types go through [`TxBuilder`] instead.
+    async fn tx_sign_multi<T>(
+        &self,
+        chain_cfg: &ChainConfig,
+        msgs: Vec<T>,
+        keys: &[&dyn ToSigner],
+        tx_options: &TxOptions,
+    ) -> Result<RawTx, AccountError>
+    where
+        T: IntoAny + Clone + Send + Sync,
+        <T as IntoAny>::Err: Send + Sync,
+    {
+        let mut builder = TxBuilder::new()
+            .memo(&tx_options.memo)
+            .timeout_height(tx_options.timeout_height.unwrap_or_default())
+            .sign_mode(tx_options.sign_mode);
+        for msg in msgs {
+            builder = builder.msg(msg)?;
+        }
+        if let Some(fee) = &tx_options.fee {
+            builder = builder.fee(fee.clone());
+        }
+
+        builder.sign(self, chain_cfg, keys).await
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use cosmrs::proto::cosmos::tx::v1beta1::{AuthInfo, TxRaw};
+
+    use super::*;
+    use crate::clients::fake::FakeChain;
+    use crate::clients::recording::{Interaction, RecordingClient};
+    use crate::modules::bank::api::BankTxCommit;
+
+    #[tokio::test]
+    async fn simulated_tx_matches_signed_tx() {
+        let cfg = FakeChain::chain_cfg();
+        let client = RecordingClient::new(FakeChain::new(&cfg.chain_id));
+        let (key, _, req) = client.inner().funded_sender().await;
+
+        let tx_options = TxOptions {
+            memo: "a long memo ".repeat(20),
+            timeout_height: Some(1_000),
+            ..Default::default()
+        };
+        client
+            .bank_send_commit(&cfg, req, &key, &tx_options)
+            .await
+            .unwrap();
+
+        let txs: Vec<TxRaw> = client
+            .fixture()
+            .interactions
+            .into_iter()
+            .filter_map(|interaction| match interaction {
+                Interaction::AbciQuery {
+                    path: Some(path),
+                    data,
+                    ..
+                } if path.ends_with("/Simulate") => {
+                    Some(SimulateRequest::decode(data.as_slice()).unwrap().tx_bytes)
+                }
+                Interaction::BroadcastTxCommit { tx, .. } => Some(tx),
+                _ => None,
+            })
+            .map(|bytes| RawTx::from_bytes(&bytes).unwrap().into())
+            .collect();
+        let [simulated, signed] = txs.as_slice() else {
+            panic!("expected a simulation and a broadcast, got {txs:?}");
+        };
+
+        // only the fee and the signature differ, the account has never signed before
+        assert_eq!(simulated.body_bytes, signed.body_bytes);
+        let signer_infos = |tx: &TxRaw| {
+            AuthInfo::decode(tx.auth_info_bytes.as_slice())
+                .unwrap()
+                .signer_infos
+        };
+        assert_eq!(signer_infos(simulated), signer_infos(signed));
+        assert!(signer_infos(simulated)[0].public_key.is_some());
+        assert_eq!(simulated.signatures, vec![Vec::<u8>::new()]);
+    }
 }
@@ -0,0 +1,744 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::{
+    auth::v1beta1::{QueryAccountRequest, QueryAccountResponse},
+    bank::v1beta1::{
+        MsgSend, QueryAllBalancesRequest, QueryAllBalancesResponse, QueryBalanceRequest,
+        QueryBalanceResponse,
+    },
+    base::{abci::v1beta1::GasInfo as ProtoGasInfo, v1beta1::Coin as ProtoCoin},
+    tx::v1beta1::{SignDoc, SimulateRequest, SimulateResponse, TxRaw},
+};
+use cosmrs::proto::traits::{Message, MessageExt};
+use cosmrs::tendermint::abci::{
+    response::{CheckTx, DeliverTx},
+    Code, Event, EventAttribute,
+};
+use cosmrs::tx::{ModeInfo, SignMode, SignerPublicKey};
+use cosmrs::Tx;
+use tendermint_rpc::endpoint::{
+    abci_query::AbciQuery,
+    broadcast::{tx_async, tx_commit, tx_sync},
+};
+
+use crate::chain::amino::StdSignDoc;
+use crate::chain::coin::Coin;
+use crate::chain::error::ChainError;
+use crate::chain::fee::Fee;
+use crate::chain::tx::RawTx;
+use crate::modules::auth::model::{Account, Address};
+use crate::signing_key::algorithm::{public_key_from_any, KeyAlgorithm};
+#[cfg(test)]
+use crate::{
+    config::cfg::ChainConfig, modules::bank::model::SendRequest, signing_key::key::UserKey,
+};
+
+use super::client::{ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync, GetErr};
+use super::mock::account_to_any;
+use super::stream::tx_hash;
+
+const MSG_SEND_URL: &str = "/cosmos.bank.v1beta1.MsgSend";
+
+// cosmos-sdk error codes, so callers can match on them like on a real chain
+const CODE_TX_DECODE: u32 = 2;
+const CODE_UNAUTHORIZED: u32 = 4;
+const CODE_INSUFFICIENT_FUNDS: u32 = 5;
+const CODE_UNKNOWN_REQUEST: u32 = 6;
+const CODE_INVALID_ADDRESS: u32 = 7;
+const CODE_INVALID_PUBKEY: u32 = 8;
+const CODE_INVALID_COINS: u32 = 10;
+const CODE_OUT_OF_GAS: u32 = 11;
+const CODE_NO_SIGNATURES: u32 = 15;
+const CODE_KEY_NOT_FOUND: u32 = 22;
+const CODE_WRONG_SEQUENCE: u32 = 32;
+
+/// A stateful in-process chain for unit tests.
+///
+/// It keeps accounts with sequence numbers and bank balances, answers the auth account,
+/// bank balance and tx simulation queries, and applies `MsgSend` on broadcast after
+/// checking the signer's sequence and signature.
+/// Broadcasted txs are applied immediately, each one in its own block.
+#[derive(Debug)]
+pub struct FakeChain {
+    chain_id: String,
+    gas_used: u64,
+    state: Mutex<State>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct State {
+    accounts: BTreeMap<Address, FakeAccount>,
+    height: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct FakeAccount {
+    account_number: u64,
+    sequence: u64,
+    pubkey: Option<PublicKey>,
+    balances: BTreeMap<String, u128>,
+}
+
+#[derive(Clone, Debug)]
+struct Failure {
+    code: u32,
+    log: String,
+}
+
+impl Failure {
+    fn new(code: u32, log: impl Into<String>) -> Self {
+        Self {
+            code,
+            log: log.into(),
+        }
+    }
+}
+
+impl State {
+    fn account_mut(&mut self, address: &Address) -> &mut FakeAccount {
+        let account_number = self.accounts.len() as u64;
+        self.accounts
+            .entry(address.clone())
+            .or_insert_with(|| FakeAccount {
+                account_number,
+                ..Default::default()
+            })
+    }
+}
+
+impl FakeAccount {
+    fn debit(&mut self, denom: &str, amount: u128) -> Result<(), Failure> {
+        let balance = self.balances.entry(denom.to_string()).or_default();
+        if *balance < amount {
+            return Err(Failure::new(
+                CODE_INSUFFICIENT_FUNDS,
+                format!("{balance}{denom} is smaller than {amount}{denom}: insufficient funds"),
+            ));
+        }
+        *balance -= amount;
+        Ok(())
+    }
+
+    fn credit(&mut self, denom: &str, amount: u128) {
+        *self.balances.entry(denom.to_string()).or_default() += amount;
+    }
+}
+
+impl FakeChain {
+    pub fn new(chain_id: impl Into<String>) -> Self {
+        Self {
+            chain_id: chain_id.into(),
+            gas_used: 100_000,
+            state: Mutex::new(State {
+                accounts: BTreeMap::new(),
+                height: 1,
+            }),
+        }
+    }
+
+    /// Sets the gas every tx uses, defaults to 100_000
+    pub fn with_gas_used(mut self, gas_used: u64) -> Self {
+        self.gas_used = gas_used;
+        self
+    }
+
+    /// Adds `coin` to the balance of `address`, creating the account if needed
+    pub fn fund(&self, address: &Address, coin: Coin) {
+        self.state
+            .lock()
+            .unwrap()
+            .account_mut(address)
+            .credit(&coin.denom.to_string(), coin.amount);
+    }
+
+    pub fn balance(&self, address: &Address, denom: &str) -> u128 {
+        self.state
+            .lock()
+            .unwrap()
+            .accounts
+            .get(address)
+            .and_then(|account| account.balances.get(denom).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn account(&self, address: &Address) -> Option<Account> {
+        self.state
+            .lock()
+            .unwrap()
+            .accounts
+            .get(address)
+            .map(|account| to_account(address, account))
+    }
+
+    /// Height of the last block, every broadcasted tx that passes the ante checks creates one
+    pub fn height(&self) -> u64 {
+        self.state.lock().unwrap().height
+    }
+
+    fn handle_query(&self, path: &str, data: &[u8]) -> Result<Vec<u8>, Failure> {
+        let state = self.state.lock().unwrap();
+
+        match path {
+            "/cosmos.auth.v1beta1.Query/Account" => {
+                let req = decode::<QueryAccountRequest>(data)?;
+                let address = parse_address(&req.address)?;
+                let account = state.accounts.get(&address).ok_or_else(|| {
+                    Failure::new(
+                        CODE_KEY_NOT_FOUND,
+                        format!("account {address} not found: key not found"),
+                    )
+                })?;
+                encode(QueryAccountResponse {
+                    account: Some(account_to_any(&to_account(&address, account))),
+                })
+            }
+
+            "/cosmos.bank.v1beta1.Query/Balance" => {
+                let req = decode::<QueryBalanceRequest>(data)?;
+                let address = parse_address(&req.address)?;
+                let amount = state
+                    .accounts
+                    .get(&address)
+                    .and_then(|account| account.balances.get(&req.denom).copied())
+                    .unwrap_or_default();
+                encode(QueryBalanceResponse {
+                    balance: Some(ProtoCoin {
+                        denom: req.denom,
+                        amount: amount.to_string(),
+                    }),
+                })
+            }
+
+            "/cosmos.bank.v1beta1.Query/AllBalances" => {
+                let req = decode::<QueryAllBalancesRequest>(data)?;
+                let address = parse_address(&req.address)?;
+                let balances = state
+                    .accounts
+                    .get(&address)
+                    .map(|account| {
+                        account
+                            .balances
+                            .iter()
+                            .filter(|(_, amount)| **amount > 0)
+                            .map(|(denom, amount)| ProtoCoin {
+                                denom: denom.clone(),
+                                amount: amount.to_string(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                encode(QueryAllBalancesResponse {
+                    balances,
+                    pagination: None,
+                })
+            }
+
+            "/cosmos.tx.v1beta1.Service/Simulate" => {
+                let req = decode::<SimulateRequest>(data)?;
+                // simulations run against a copy of the state and never persist
+                let mut state = state.clone();
+                let (gas_wanted, _) = self.process(&mut state, &req.tx_bytes, true)??;
+                encode(SimulateResponse {
+                    gas_info: Some(ProtoGasInfo {
+                        gas_wanted,
+                        gas_used: self.gas_used,
+                    }),
+                    result: None,
+                })
+            }
+
+            _ => Err(Failure::new(
+                CODE_UNKNOWN_REQUEST,
+                format!("unknown query path: {path}"),
+            )),
+        }
+    }
+
+    /// Runs the ante checks and then the msgs of a tx against `state`.
+    /// The outer result fails when the ante checks fail, in which case nothing is changed.
+    /// Otherwise the fee and sequence are always applied, and the msgs only when they all succeed.
+    #[allow(clippy::type_complexity)]
+    fn process(
+        &self,
+        state: &mut State,
+        tx_bytes: &[u8],
+        simulate: bool,
+    ) -> Result<Result<(u64, Vec<Event>), Failure>, Failure> {
+        let raw =
+            TxRaw::decode(tx_bytes).map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))?;
+        let tx =
+            Tx::from_bytes(tx_bytes).map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))?;
+
+        let sends = tx
+            .body
+            .messages
+            .iter()
+            .map(|msg| {
+                if msg.type_url != MSG_SEND_URL {
+                    return Err(Failure::new(
+                        CODE_UNKNOWN_REQUEST,
+                        format!("unsupported msg type: {}", msg.type_url),
+                    ));
+                }
+                decode::<MsgSend>(&msg.value)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let signer = parse_address(
+            &sends
+                .first()
+                .ok_or_else(|| Failure::new(CODE_UNKNOWN_REQUEST, "tx has no msgs"))?
+                .from_address,
+        )?;
+        if sends.iter().any(|msg| msg.from_address != signer.as_ref()) {
+            return Err(Failure::new(
+                CODE_UNAUTHORIZED,
+                "all msgs must have the same signer",
+            ));
+        }
+
+        let signer_info = tx
+            .auth_info
+            .signer_infos
+            .first()
+            .ok_or_else(|| Failure::new(CODE_NO_SIGNATURES, "no signer info"))?;
+
+        let mut ante = state.clone();
+        let account = ante.accounts.get_mut(&signer).ok_or_else(|| {
+            Failure::new(
+                CODE_KEY_NOT_FOUND,
+                format!("account {signer} not found: key not found"),
+            )
+        })?;
+
+        if signer_info.sequence != account.sequence {
+            return Err(Failure::new(
+                CODE_WRONG_SEQUENCE,
+                format!(
+                    "account sequence mismatch, expected {}, got {}: incorrect account sequence",
+                    account.sequence, signer_info.sequence
+                ),
+            ));
+        }
+
+        // simulations may leave out the public key of a fresh account, like the cosmos-sdk allows
+        let (pubkey, algorithm) = match &signer_info.public_key {
+            Some(SignerPublicKey::Single(pubkey)) => (Some(*pubkey), KeyAlgorithm::Secp256k1),
+            Some(SignerPublicKey::Any(any)) => {
+                let algorithm = KeyAlgorithm::from_type_url(&any.type_url).ok_or_else(|| {
+                    Failure::new(
+                        CODE_INVALID_PUBKEY,
+                        format!("unsupported public key type {}", any.type_url),
+                    )
+                })?;
+                let pubkey = public_key_from_any(any.clone())
+                    .map_err(|e| Failure::new(CODE_INVALID_PUBKEY, e.to_string()))?;
+                (Some(pubkey), algorithm)
+            }
+            // the stored key does not record its type, so go by the address it derives
+            None => {
+                let algorithm = [KeyAlgorithm::Secp256k1, KeyAlgorithm::EthSecp256k1]
+                    .into_iter()
+                    .find(|algorithm| {
+                        account.pubkey.is_some_and(|pubkey| {
+                            algorithm.address(&pubkey, signer.prefix()).ok() == Some(signer.clone())
+                        })
+                    })
+                    .unwrap_or_default();
+                (account.pubkey, algorithm)
+            }
+        };
+        match pubkey {
+            Some(pubkey) => {
+                let pubkey_addr = algorithm
+                    .address(&pubkey, signer.prefix())
+                    .map_err(|e| Failure::new(CODE_INVALID_PUBKEY, e.to_string()))?;
+                if pubkey_addr != signer {
+                    return Err(Failure::new(
+                        CODE_INVALID_PUBKEY,
+                        format!("pubkey does not match signer address {signer}"),
+                    ));
+                }
+            }
+            None if simulate => {}
+            None => {
+                return Err(Failure::new(
+                    CODE_INVALID_PUBKEY,
+                    "pubkey on account is not set",
+                ))
+            }
+        }
+
+        // simulations are sent with an empty signature and without a gas limit
+        if let (false, Some(pubkey)) = (simulate, pubkey) {
+            let sign_doc = match &signer_info.mode_info {
+                ModeInfo::Single(single) if single.mode == SignMode::LegacyAminoJson => {
+                    let fee = Fee::try_from(tx.auth_info.fee.clone())
+                        .map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))?;
+                    StdSignDoc::new(
+                        &tx.body.messages,
+                        tx.body.timeout_height.value(),
+                        &tx.body.memo,
+                        account.account_number,
+                        account.sequence,
+                        &fee,
+                        &self.chain_id,
+                    )
+                    .and_then(|doc| doc.to_sign_bytes())
+                    .map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))?
+                }
+                _ => SignDoc {
+                    body_bytes: raw.body_bytes.clone(),
+                    auth_info_bytes: raw.auth_info_bytes.clone(),
+                    chain_id: self.chain_id.clone(),
+                    account_number: account.account_number,
+                }
+                .encode_to_vec(),
+            };
+
+            let signature = raw
+                .signatures
+                .first()
+                .ok_or_else(|| Failure::new(CODE_NO_SIGNATURES, "no signatures"))?;
+            if !algorithm.verify(&pubkey, &sign_doc, signature) {
+                return Err(Failure::new(
+                    CODE_UNAUTHORIZED,
+                    format!(
+                        "signature verification failed; please verify account number ({}) and chain-id ({}): unauthorized",
+                        account.account_number, self.chain_id
+                    ),
+                ));
+            }
+
+            if tx.auth_info.fee.gas_limit < self.gas_used {
+                return Err(Failure::new(
+                    CODE_OUT_OF_GAS,
+                    format!(
+                        "out of gas: gasWanted: {}, gasUsed: {}",
+                        tx.auth_info.fee.gas_limit, self.gas_used
+                    ),
+                ));
+            }
+        }
+
+        for coin in &tx.auth_info.fee.amount {
+            account.debit(coin.denom.as_ref(), coin.amount)?;
+        }
+        account.sequence += 1;
+        account.pubkey = pubkey;
+        ante.height += 1;
+        *state = ante;
+
+        let mut delivered = state.clone();
+        let mut events = vec![event(
+            "message",
+            &[
+                ("action", MSG_SEND_URL),
+                ("sender", signer.as_ref()),
+                ("module", "bank"),
+            ],
+        )];
+        for msg in &sends {
+            match apply_send(&mut delivered, msg) {
+                Ok(transfer) => events.push(transfer),
+                Err(failure) => return Ok(Err(failure)),
+            }
+        }
+        *state = delivered;
+
+        Ok(Ok((tx.auth_info.fee.gas_limit, events)))
+    }
+
+    /// Applies a broadcasted tx, returning the check and deliver results
+    fn deliver(&self, raw_tx: &RawTx) -> Result<(CheckTx, DeliverTx, u64), ChainError> {
+        let tx_bytes = raw_tx.to_bytes()?;
+        let mut state = self.state.lock().unwrap();
+
+        let (check_tx, deliver_tx) = match self.process(&mut state, &tx_bytes, false) {
+            Err(failure) => (
+                CheckTx {
+                    code: Code::from(failure.code),
+                    log: failure.log,
+                    ..Default::default()
+                },
+                DeliverTx::default(),
+            ),
+            Ok(Ok((gas_wanted, events))) => (
+                CheckTx::default(),
+                DeliverTx {
+                    gas_wanted: gas_wanted as i64,
+                    gas_used: self.gas_used as i64,
+                    events,
+                    ..Default::default()
+                },
+            ),
+            Ok(Err(failure)) => (
+                CheckTx::default(),
+                DeliverTx {
+                    code: Code::from(failure.code),
+                    log: failure.log,
+                    gas_used: self.gas_used as i64,
+                    ..Default::default()
+                },
+            ),
+        };
+
+        Ok((check_tx, deliver_tx, state.height))
+    }
+}
+
+fn to_account(address: &Address, account: &FakeAccount) -> Account {
+    Account {
+        address: address.clone(),
+        pubkey: account.pubkey,
+        account_number: account.account_number,
+        sequence: account.sequence,
+    }
+}
+
+fn apply_send(state: &mut State, msg: &MsgSend) -> Result<Event, Failure> {
+    let from = parse_address(&msg.from_address)?;
+    let to = parse_address(&msg.to_address)?;
+
+    let mut amounts = vec![];
+    for coin in &msg.amount {
+        let amount: u128 = coin.amount.parse().map_err(|_| {
+            Failure::new(
+                CODE_INVALID_COINS,
+                format!("invalid amount: {}", coin.amount),
+            )
+        })?;
+        state.account_mut(&from).debit(&coin.denom, amount)?;
+        state.account_mut(&to).credit(&coin.denom, amount);
+        amounts.push(format!("{}{}", amount, coin.denom));
+    }
+
+    Ok(event(
+        "transfer",
+        &[
+            ("recipient", to.as_ref()),
+            ("sender", from.as_ref()),
+            ("amount", &amounts.join(",")),
+        ],
+    ))
+}
+
+fn event(kind: &str, attributes: &[(&str, &str)]) -> Event {
+    Event {
+        kind: kind.to_string(),
+        attributes: attributes
+            .iter()
+            .map(|(key, value)| EventAttribute {
+                key: key.to_string(),
+                value: value.to_string(),
+                index: true,
+            })
+            .collect(),
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address, Failure> {
+    address
+        .parse()
+        .map_err(|_| Failure::new(CODE_INVALID_ADDRESS, format!("invalid address: {address}")))
+}
+
+fn decode<T: Message + Default>(data: &[u8]) -> Result<T, Failure> {
+    T::decode(data).map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))
+}
+
+fn encode<T: Message>(msg: T) -> Result<Vec<u8>, Failure> {
+    msg.to_bytes()
+        .map_err(|e| Failure::new(CODE_TX_DECODE, e.to_string()))
+}
+
+#[async_trait]
+impl ClientAbciQuery for FakeChain {
+    type Response = AbciQuery;
+
+    async fn abci_query<V>(
+        &self,
+        path: Option<String>,
+        data: V,
+        _height: Option<u32>,
+        _prove: bool,
+    ) -> Result<Self::Response, ChainError>
+    where
+        V: Into<Vec<u8>> + Send,
+    {
+        let path = path.unwrap_or_default();
+        let res = match self.handle_query(&path, &data.into()) {
+            Ok(value) => AbciQuery {
+                value,
+                height: self.height().try_into()?,
+                ..Default::default()
+            },
+            Err(failure) => AbciQuery {
+                code: Code::from(failure.code),
+                log: failure.log,
+                height: self.height().try_into()?,
+                ..Default::default()
+            },
+        };
+        res.get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxCommit for FakeChain {
+    type Response = tx_commit::Response;
+
+    async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let (check_tx, deliver_tx, height) = self.deliver(raw_tx)?;
+        tx_commit::Response {
+            check_tx,
+            deliver_tx,
+            hash: tx_hash(&raw_tx.to_bytes()?),
+            height: height.try_into()?,
+        }
+        .get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxSync for FakeChain {
+    type Response = tx_sync::Response;
+
+    async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let (check_tx, _, _) = self.deliver(raw_tx)?;
+        tx_sync::Response {
+            code: check_tx.code,
+            data: check_tx.data,
+            log: check_tx.log,
+            hash: tx_hash(&raw_tx.to_bytes()?),
+        }
+        .get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxAsync for FakeChain {
+    type Response = tx_async::Response;
+
+    async fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let (check_tx, _, _) = self.deliver(raw_tx)?;
+        tx_async::Response {
+            code: check_tx.code,
+            data: check_tx.data,
+            log: check_tx.log,
+            hash: tx_hash(&raw_tx.to_bytes()?),
+        }
+        .get_err()
+    }
+}
+
+/// Shared setup of the unit tests that run against a fake chain
+#[cfg(test)]
+impl FakeChain {
+    /// A `utest` chain with the `juno` prefix, gas at 0.25utest with a 1.5 adjustment
+    pub(crate) fn chain_cfg() -> ChainConfig {
+        ChainConfig::new("utest", "juno", "test-1", "m/44'/118'/0'/0/0", 0.25, 1.5)
+    }
+
+    /// `amount` of the fee denom of [`FakeChain::chain_cfg`]
+    pub(crate) fn coin(amount: u128) -> Coin {
+        Coin {
+            denom: "utest".parse().unwrap(),
+            amount,
+        }
+    }
+
+    /// A random key funded with 1_000_000utest, its address under [`FakeChain::chain_cfg`],
+    /// and a request sending 100utest from it
+    pub(crate) async fn funded_sender(&self) -> (UserKey, Address, SendRequest) {
+        let key = UserKey::random_mnemonic("sender".to_string());
+        let sender = key.to_chain_addr(&Self::chain_cfg()).await.unwrap();
+        self.fund(&sender, Self::coin(1_000_000));
+        let req = SendRequest {
+            from: sender.clone(),
+            to: Address::new("juno", &[2; 20]).unwrap(),
+            amounts: vec![Self::coin(100)],
+        };
+        (key, sender, req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chain::{error::ChainError, request::TxOptions},
+        modules::{
+            auth::error::AccountError,
+            bank::{
+                api::{BankQuery, BankTxCommit},
+                error::BankError,
+            },
+        },
+        signing_key::{key::UserKey, signer::ToSigner},
+    };
+
+    use super::FakeChain;
+
+    #[tokio::test]
+    async fn bank_send_commit_end_to_end() {
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+
+        // the second send only succeeds if the sequence was bumped by the first one
+        for _ in 0..2 {
+            chain
+                .bank_send_commit(&cfg, req.clone(), &key, &TxOptions::default())
+                .await
+                .unwrap();
+        }
+
+        // gas limit of 100_000 * 1.5 at 0.25utest
+        assert_eq!(
+            chain.balance(&sender, "utest"),
+            1_000_000 - 2 * (100 + 37_500)
+        );
+        assert_eq!(chain.account(&sender).unwrap().sequence, 2);
+
+        let res = chain
+            .bank_query_balance(req.to.clone(), "utest".parse().unwrap(), None)
+            .await
+            .unwrap();
+        assert_eq!(res.value.balance, FakeChain::coin(200));
+
+        // a stale sequence is rejected
+        let mut stale = chain.account(&sender).unwrap();
+        stale.sequence = 0;
+        let tx_options = TxOptions {
+            account: Some(stale),
+            ..Default::default()
+        };
+        let err = chain
+            .bank_send_commit(&cfg, req.clone(), &key, &tx_options)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BankError::AccountError(AccountError::ChainError(ChainError::AbciQuery { .. }))
+        ));
+
+        // signatures from another key are rejected
+        let other = UserKey::random_mnemonic("other".to_string());
+        let tx_options = TxOptions {
+            account: chain.account(&sender),
+            ..Default::default()
+        };
+        let err = chain
+            .bank_send_commit(&cfg, req, &other, &tx_options)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BankError::ChainError(ChainError::TxCommit { .. })
+        ));
+        assert_eq!(chain.account(&sender).unwrap().sequence, 2);
+    }
+}
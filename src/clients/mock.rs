@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use cosmrs::proto::cosmos::{
+    auth::v1beta1::QueryAccountResponse, base::abci::v1beta1::GasInfo as ProtoGasInfo,
+    tx::v1beta1::SimulateResponse,
+};
+use cosmrs::proto::traits::MessageExt;
+use cosmrs::tendermint::{abci::response::DeliverTx, Hash};
+use mockall::mock;
+use tendermint_rpc::endpoint::{
+    abci_query::AbciQuery,
+    broadcast::{tx_async, tx_commit, tx_sync},
+};
+
+use crate::chain::error::ChainError;
+use crate::chain::fee::GasInfo;
+use crate::chain::tx::RawTx;
+use crate::modules::auth::model::Account;
+
+use super::client::{ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync, GetErr};
+
+mock! {
+    /// Mock client for unit tests. The client traits forward to these non-generic methods,
+    /// so expectations are set with `expect_abci_query`, `expect_broadcast_tx_commit`, etc.
+    pub Client {
+        pub fn abci_query(
+            &self,
+            path: Option<String>,
+            data: Vec<u8>,
+            height: Option<u32>,
+            prove: bool,
+        ) -> Result<AbciQuery, ChainError>;
+
+        pub fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<tx_commit::Response, ChainError>;
+
+        pub fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<tx_sync::Response, ChainError>;
+
+        pub fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<tx_async::Response, ChainError>;
+    }
+}
+
+impl MockClient {
+    /// Answers every auth account query with `account`
+    pub fn expect_account(&mut self, account: Account) -> &mut Self {
+        let value = QueryAccountResponse {
+            account: Some(account_to_any(&account)),
+        }
+        .to_bytes()
+        .unwrap();
+
+        self.expect_abci_query()
+            .withf(|path, _, _, _| path.as_deref() == Some("/cosmos.auth.v1beta1.Query/Account"))
+            .returning(move |_, _, _, _| Ok(abci_query_response(value.clone())));
+        self
+    }
+
+    /// Answers every tx simulation with `gas_info`
+    pub fn expect_simulate(&mut self, gas_info: GasInfo) -> &mut Self {
+        let value = SimulateResponse {
+            gas_info: Some(ProtoGasInfo::from(gas_info)),
+            result: None,
+        }
+        .to_bytes()
+        .unwrap();
+
+        self.expect_abci_query()
+            .withf(|path, _, _, _| path.as_deref() == Some("/cosmos.tx.v1beta1.Service/Simulate"))
+            .returning(move |_, _, _, _| Ok(abci_query_response(value.clone())));
+        self
+    }
+
+    /// Answers every commit broadcast with `deliver_tx` as the result
+    pub fn expect_commit(&mut self, deliver_tx: DeliverTx) -> &mut Self {
+        self.expect_broadcast_tx_commit().returning(move |_| {
+            Ok(tx_commit::Response {
+                check_tx: Default::default(),
+                deliver_tx: deliver_tx.clone(),
+                hash: Hash::None,
+                height: Default::default(),
+            })
+        });
+        self
+    }
+}
+
+fn abci_query_response(value: Vec<u8>) -> AbciQuery {
+    AbciQuery {
+        value,
+        ..Default::default()
+    }
+}
+
+#[cfg(not(feature = "injective"))]
+pub(crate) fn account_to_any(account: &Account) -> cosmrs::Any {
+    use cosmrs::proto::cosmos::auth::v1beta1::BaseAccount;
+
+    BaseAccount {
+        address: account.address.to_string(),
+        pub_key: account.pubkey.as_ref().map(|pk| pk.to_any().unwrap()),
+        account_number: account.account_number,
+        sequence: account.sequence,
+    }
+    .to_any()
+    .unwrap()
+}
+
+#[cfg(feature = "injective")]
+pub(crate) fn account_to_any(account: &Account) -> cosmrs::Any {
+    use injective_std::types::cosmos::auth::v1beta1::BaseAccount;
+    use injective_std::types::injective::types::v1beta1::EthAccount;
+
+    // the public key is left out, `tx_sign` derives it from the signing key instead
+    let eth_account = EthAccount {
+        base_account: Some(BaseAccount {
+            address: account.address.to_string(),
+            pub_key: None,
+            account_number: account.account_number,
+            sequence: account.sequence,
+        }),
+        code_hash: vec![],
+    };
+
+    cosmrs::Any {
+        type_url: "/injective.types.v1beta1.EthAccount".to_string(),
+        value: prost::Message::encode_to_vec(&eth_account),
+    }
+}
+
+#[async_trait]
+impl ClientAbciQuery for MockClient {
+    type Response = AbciQuery;
+
+    async fn abci_query<V>(
+        &self,
+        path: Option<String>,
+        data: V,
+        height: Option<u32>,
+        prove: bool,
+    ) -> Result<Self::Response, ChainError>
+    where
+        V: Into<Vec<u8>> + Send,
+    {
+        self.abci_query(path, data.into(), height, prove)?.get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxCommit for MockClient {
+    type Response = tx_commit::Response;
+
+    async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        self.broadcast_tx_commit(raw_tx)?.get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxSync for MockClient {
+    type Response = tx_sync::Response;
+
+    async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        self.broadcast_tx_sync(raw_tx)?.get_err()
+    }
+}
+
+#[async_trait]
+impl ClientTxAsync for MockClient {
+    type Response = tx_async::Response;
+
+    async fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        self.broadcast_tx_async(raw_tx)?.get_err()
+    }
+}
@@ -1,5 +1,23 @@
+pub mod account_cache;
+
 pub mod client;
 
+#[cfg(feature = "mock")]
+pub mod fake;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub mod recording;
+
 pub mod response;
 
+pub mod scanner;
+
+pub mod sequence;
+
+pub mod stream;
+
 pub mod tendermint_rpc;
+
+pub mod tx_builder;
@@ -0,0 +1,417 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::endpoint::{
+    abci_query::AbciQuery,
+    broadcast::{tx_async, tx_commit, tx_sync},
+};
+
+use crate::chain::error::ChainError;
+use crate::chain::tx::RawTx;
+
+use super::client::{ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync};
+
+/// A single request/response pair sent through a [`RecordingClient`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Interaction {
+    AbciQuery {
+        path: Option<String>,
+        #[serde(with = "hex")]
+        data: Vec<u8>,
+        height: Option<u32>,
+        prove: bool,
+        #[serde(flatten)]
+        outcome: Outcome<AbciQuery>,
+    },
+    BroadcastTxCommit {
+        #[serde(with = "hex")]
+        tx: Vec<u8>,
+        #[serde(flatten)]
+        outcome: Outcome<tx_commit::Response>,
+    },
+    BroadcastTxSync {
+        #[serde(with = "hex")]
+        tx: Vec<u8>,
+        #[serde(flatten)]
+        outcome: Outcome<tx_sync::Response>,
+    },
+    BroadcastTxAsync {
+        #[serde(with = "hex")]
+        tx: Vec<u8>,
+        #[serde(flatten)]
+        outcome: Outcome<tx_async::Response>,
+    },
+}
+
+/// What a recorded request returned
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome<T> {
+    Response(T),
+    /// The request failed, only the message of its error is kept
+    Error(String),
+}
+
+impl<T: Clone> Outcome<T> {
+    fn new(res: &Result<T, ChainError>) -> Self {
+        match res {
+            Ok(response) => Outcome::Response(response.clone()),
+            Err(e) => Outcome::Error(e.to_string()),
+        }
+    }
+}
+
+impl<T> Outcome<T> {
+    fn replay(self) -> Result<T, ChainError> {
+        match self {
+            Outcome::Response(response) => Ok(response),
+            Outcome::Error(message) => Err(ChainError::Recorded { message }),
+        }
+    }
+}
+
+impl Interaction {
+    fn is_abci_query(
+        &self,
+        path: &Option<String>,
+        data: &[u8],
+        height: Option<u32>,
+        prove: bool,
+    ) -> bool {
+        matches!(self, Interaction::AbciQuery { path: p, data: d, height: h, prove: pr, .. }
+            if p == path && d == data && *h == height && *pr == prove)
+    }
+}
+
+/// Interactions in the order they were recorded
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Fixture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ChainError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| ChainError::fixture(path, e))?;
+        serde_json::from_str(&json).map_err(|e| ChainError::fixture(path, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ChainError> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|e| ChainError::fixture(path, e))?;
+        std::fs::write(path, json).map_err(|e| ChainError::fixture(path, e))
+    }
+}
+
+/// Wraps a client and records every `abci_query` and broadcast with its response or error,
+/// so they can be replayed offline with a [`ReplayClient`].
+#[derive(Debug)]
+pub struct RecordingClient<C> {
+    inner: C,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl<C> RecordingClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            interactions: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns everything recorded so far
+    pub fn fixture(&self) -> Fixture {
+        Fixture {
+            interactions: self.interactions.lock().unwrap().clone(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ChainError> {
+        self.fixture().save(path)
+    }
+
+    fn record(&self, interaction: Interaction) {
+        self.interactions.lock().unwrap().push(interaction);
+    }
+}
+
+#[async_trait]
+impl<C> ClientAbciQuery for RecordingClient<C>
+where
+    C: ClientAbciQuery<Response = AbciQuery> + Send + Sync,
+{
+    type Response = AbciQuery;
+
+    async fn abci_query<V>(
+        &self,
+        path: Option<String>,
+        data: V,
+        height: Option<u32>,
+        prove: bool,
+    ) -> Result<Self::Response, ChainError>
+    where
+        V: Into<Vec<u8>> + Send,
+    {
+        let data = data.into();
+        let res =
+            ClientAbciQuery::abci_query(&self.inner, path.clone(), data.clone(), height, prove)
+                .await;
+        self.record(Interaction::AbciQuery {
+            path,
+            data,
+            height,
+            prove,
+            outcome: Outcome::new(&res),
+        });
+        res
+    }
+}
+
+#[async_trait]
+impl<C> ClientTxCommit for RecordingClient<C>
+where
+    C: ClientTxCommit<Response = tx_commit::Response> + Send + Sync,
+{
+    type Response = tx_commit::Response;
+
+    async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let tx = raw_tx.to_bytes()?;
+        let res = ClientTxCommit::broadcast_tx_commit(&self.inner, raw_tx).await;
+        self.record(Interaction::BroadcastTxCommit {
+            tx,
+            outcome: Outcome::new(&res),
+        });
+        res
+    }
+}
+
+#[async_trait]
+impl<C> ClientTxSync for RecordingClient<C>
+where
+    C: ClientTxSync<Response = tx_sync::Response> + Send + Sync,
+{
+    type Response = tx_sync::Response;
+
+    async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let tx = raw_tx.to_bytes()?;
+        let res = ClientTxSync::broadcast_tx_sync(&self.inner, raw_tx).await;
+        self.record(Interaction::BroadcastTxSync {
+            tx,
+            outcome: Outcome::new(&res),
+        });
+        res
+    }
+}
+
+#[async_trait]
+impl<C> ClientTxAsync for RecordingClient<C>
+where
+    C: ClientTxAsync<Response = tx_async::Response> + Send + Sync,
+{
+    type Response = tx_async::Response;
+
+    async fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        let tx = raw_tx.to_bytes()?;
+        let res = ClientTxAsync::broadcast_tx_async(&self.inner, raw_tx).await;
+        self.record(Interaction::BroadcastTxAsync {
+            tx,
+            outcome: Outcome::new(&res),
+        });
+        res
+    }
+}
+
+/// Answers requests from a [`Fixture`] instead of a node.
+/// Each recorded interaction is used at most once, the first unused one matching the request wins.
+#[derive(Debug)]
+pub struct ReplayClient {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayClient {
+    pub fn new(fixture: Fixture) -> Self {
+        Self {
+            interactions: Mutex::new(fixture.interactions.into_iter().map(Some).collect()),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ChainError> {
+        Ok(Self::new(Fixture::load(path)?))
+    }
+
+    /// Returns the number of recorded interactions that have not been replayed yet
+    pub fn remaining(&self) -> usize {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|i| i.is_some())
+            .count()
+    }
+
+    fn take(
+        &self,
+        request: &str,
+        matches: impl Fn(&Interaction) -> bool,
+    ) -> Result<Interaction, ChainError> {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|i| matches!(i, Some(interaction) if matches(interaction)))
+            .and_then(Option::take)
+            .ok_or_else(|| ChainError::Fixture {
+                message: format!("no recorded response left for {request}"),
+            })
+    }
+
+    fn take_tx(&self, method: &str, raw_tx: &RawTx) -> Result<Interaction, ChainError> {
+        let bytes = raw_tx.to_bytes()?;
+        self.take(&format!("{method} of tx {}", hex::encode(&bytes)), |i| {
+            let tx = match i {
+                Interaction::BroadcastTxCommit { tx, .. } if method == "broadcast_tx_commit" => tx,
+                Interaction::BroadcastTxSync { tx, .. } if method == "broadcast_tx_sync" => tx,
+                Interaction::BroadcastTxAsync { tx, .. } if method == "broadcast_tx_async" => tx,
+                _ => return false,
+            };
+            *tx == bytes
+        })
+    }
+}
+
+#[async_trait]
+impl ClientAbciQuery for ReplayClient {
+    type Response = AbciQuery;
+
+    async fn abci_query<V>(
+        &self,
+        path: Option<String>,
+        data: V,
+        height: Option<u32>,
+        prove: bool,
+    ) -> Result<Self::Response, ChainError>
+    where
+        V: Into<Vec<u8>> + Send,
+    {
+        let data = data.into();
+        let request = format!("abci_query {:?} with data {}", path, hex::encode(&data));
+        match self.take(&request, |i| i.is_abci_query(&path, &data, height, prove))? {
+            Interaction::AbciQuery { outcome, .. } => outcome.replay(),
+            _ => unreachable!("only abci queries match"),
+        }
+    }
+}
+
+#[async_trait]
+impl ClientTxCommit for ReplayClient {
+    type Response = tx_commit::Response;
+
+    async fn broadcast_tx_commit(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        match self.take_tx("broadcast_tx_commit", raw_tx)? {
+            Interaction::BroadcastTxCommit { outcome, .. } => outcome.replay(),
+            _ => unreachable!("only commit broadcasts match"),
+        }
+    }
+}
+
+#[async_trait]
+impl ClientTxSync for ReplayClient {
+    type Response = tx_sync::Response;
+
+    async fn broadcast_tx_sync(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        match self.take_tx("broadcast_tx_sync", raw_tx)? {
+            Interaction::BroadcastTxSync { outcome, .. } => outcome.replay(),
+            _ => unreachable!("only sync broadcasts match"),
+        }
+    }
+}
+
+#[async_trait]
+impl ClientTxAsync for ReplayClient {
+    type Response = tx_async::Response;
+
+    async fn broadcast_tx_async(&self, raw_tx: &RawTx) -> Result<Self::Response, ChainError> {
+        match self.take_tx("broadcast_tx_async", raw_tx)? {
+            Interaction::BroadcastTxAsync { outcome, .. } => outcome.replay(),
+            _ => unreachable!("only async broadcasts match"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query_fixture() -> Fixture {
+        Fixture {
+            interactions: vec![Interaction::AbciQuery {
+                path: Some("/cosmos.bank.v1beta1.Query/Balance".to_string()),
+                data: vec![1, 2, 3],
+                height: None,
+                prove: false,
+                outcome: Outcome::Response(AbciQuery {
+                    value: vec![4, 5, 6],
+                    ..Default::default()
+                }),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn records_and_replays_abci_queries() {
+        let path = Some("/cosmos.bank.v1beta1.Query/Balance".to_string());
+        let recorder = RecordingClient::new(ReplayClient::new(query_fixture()));
+
+        let res = recorder
+            .abci_query(path.clone(), vec![1, 2, 3], None, false)
+            .await
+            .unwrap();
+        assert_eq!(res.value, vec![4, 5, 6]);
+        assert_eq!(recorder.inner().remaining(), 0);
+
+        // every recorded response is only replayed once
+        assert!(recorder
+            .abci_query(path.clone(), vec![1, 2, 3], None, false)
+            .await
+            .is_err());
+
+        let json = serde_json::to_string(&recorder.fixture()).unwrap();
+        let replay = ReplayClient::new(serde_json::from_str(&json).unwrap());
+        assert!(replay
+            .abci_query(path.clone(), vec![7], None, false)
+            .await
+            .is_err());
+        let res = replay
+            .abci_query(path, vec![1, 2, 3], None, false)
+            .await
+            .unwrap();
+        assert_eq!(res.value, vec![4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn records_and_replays_errors() {
+        let path = Some("/cosmos.bank.v1beta1.Query/Balance".to_string());
+        let recorder = RecordingClient::new(ReplayClient::new(Fixture::default()));
+
+        let err = recorder
+            .abci_query(path.clone(), vec![1, 2, 3], None, false)
+            .await
+            .unwrap_err();
+        let message = err.to_string();
+
+        let json = serde_json::to_string(&recorder.fixture()).unwrap();
+        let replay = ReplayClient::new(serde_json::from_str(&json).unwrap());
+        match replay.abci_query(path, vec![1, 2, 3], None, false).await {
+            Err(ChainError::Recorded { message: recorded }) => assert_eq!(recorded, message),
+            res => panic!("expected the recorded error, got {res:?}"),
+        }
+        assert_eq!(replay.remaining(), 0);
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use cosmrs::tendermint::{
+    abci::{Event, EventAttribute},
+    block::Height,
+    Hash,
+};
+use futures::{stream, Stream};
+use tendermint_rpc::Client;
+use tokio::sync::Mutex;
+
+use crate::chain::error::ChainError;
+use crate::modules::auth::model::Address;
+
+use super::stream::tx_hash;
+
+/// A wasm event emitted by one of the scanned contracts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub height: u64,
+    pub tx_hash: Hash,
+    pub contract: Address,
+    /// `wasm`, or `wasm-<type>` for custom events
+    pub kind: String,
+    pub action: Option<String>,
+    /// Sender of the tx, taken from its `message` event
+    pub sender: Option<Address>,
+    pub attributes: Vec<EventAttribute>,
+}
+
+/// Selects which wasm events a [`ContractScanner`] yields
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanFilter {
+    pub contracts: Vec<Address>,
+    /// Only yield events with one of these `action` attributes. `None` yields every action.
+    pub actions: Option<Vec<String>>,
+}
+
+impl ScanFilter {
+    pub fn new(contracts: Vec<Address>) -> Self {
+        Self {
+            contracts,
+            actions: None,
+        }
+    }
+
+    pub fn with_actions(mut self, actions: Vec<String>) -> Self {
+        self.actions = Some(actions);
+        self
+    }
+
+    /// Extracts the matching wasm events from the events of a single tx
+    pub fn contract_events(
+        &self,
+        height: u64,
+        tx_hash: Hash,
+        events: &[Event],
+    ) -> Vec<ContractEvent> {
+        let sender = find_attribute(events, "message", "sender")
+            .and_then(|sender| Address::from_str(sender).ok());
+
+        events
+            .iter()
+            .filter(|event| event.kind == "wasm" || event.kind.starts_with("wasm-"))
+            .filter_map(|event| {
+                let contract = attribute(event, "_contract_address")
+                    .and_then(|addr| Address::from_str(addr).ok())
+                    .filter(|addr| self.contracts.contains(addr))?;
+
+                let action = attribute(event, "action").map(ToString::to_string);
+                if let Some(actions) = &self.actions {
+                    if !action.as_ref().is_some_and(|a| actions.contains(a)) {
+                        return None;
+                    }
+                }
+
+                Some(ContractEvent {
+                    height,
+                    tx_hash,
+                    contract,
+                    kind: event.kind.clone(),
+                    action,
+                    sender: sender.clone(),
+                    attributes: event.attributes.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn attribute<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+}
+
+fn find_attribute<'a>(events: &'a [Event], kind: &str, key: &str) -> Option<&'a str> {
+    events
+        .iter()
+        .filter(|event| event.kind == kind)
+        .find_map(|event| attribute(event, key))
+}
+
+/// Stores the last fully scanned height, so a restarted scanner resumes where it stopped
+#[async_trait]
+pub trait Checkpoint: Send + Sync {
+    async fn load(&self) -> Result<Option<u64>, ChainError>;
+
+    async fn save(&self, height: u64) -> Result<(), ChainError>;
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryCheckpoint {
+    height: Mutex<Option<u64>>,
+}
+
+#[async_trait]
+impl Checkpoint for MemoryCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, ChainError> {
+        Ok(*self.height.lock().await)
+    }
+
+    async fn save(&self, height: u64) -> Result<(), ChainError> {
+        *self.height.lock().await = Some(height);
+        Ok(())
+    }
+}
+
+/// Keeps the height as plain text in a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileCheckpoint {
+    pub path: PathBuf,
+}
+
+impl FileCheckpoint {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl Checkpoint for FileCheckpoint {
+    async fn load(&self) -> Result<Option<u64>, ChainError> {
+        match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| ChainError::checkpoint(&self.path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ChainError::checkpoint(&self.path, e)),
+        }
+    }
+
+    async fn save(&self, height: u64) -> Result<(), ChainError> {
+        // write then rename, so a crash never leaves a truncated checkpoint behind
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, height.to_string())
+            .await
+            .map_err(|e| ChainError::checkpoint(&tmp, e))?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(|e| ChainError::checkpoint(&self.path, e))
+    }
+}
+
+/// Walks every height in order from `start_height`, yielding the wasm events of the filtered contracts.
+pub struct ContractScanner<C, K> {
+    pub client: C,
+    pub checkpoint: K,
+    pub filter: ScanFilter,
+    /// Height to start from when the checkpoint is empty
+    pub start_height: u64,
+    /// How long to wait for new blocks once the scanner has caught up
+    pub poll_interval: Duration,
+}
+
+impl<C, K> ContractScanner<C, K>
+where
+    C: Client + Send + Sync,
+    K: Checkpoint,
+{
+    pub fn new(client: C, checkpoint: K, filter: ScanFilter, start_height: u64) -> Self {
+        Self {
+            client,
+            checkpoint,
+            filter,
+            start_height,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Returns the matching events of every tx at `height`
+    pub async fn scan_height(&self, height: u64) -> Result<Vec<ContractEvent>, ChainError> {
+        let block = self.client.block(Height::try_from(height)?).await?.block;
+        let results = self.client.block_results(Height::try_from(height)?).await?;
+
+        Ok(block
+            .data
+            .iter()
+            .zip(results.txs_results.unwrap_or_default())
+            .flat_map(|(tx, result)| {
+                self.filter
+                    .contract_events(height, tx_hash(tx), &result.events)
+            })
+            .collect())
+    }
+
+    /// Streams the matching events forever, starting after the checkpointed height.
+    /// A height is checkpointed once all of its events have been pulled from the stream.
+    /// Errors are yielded, and the failed height is retried on the next poll.
+    pub fn into_stream(self) -> impl Stream<Item = Result<ContractEvent, ChainError>> {
+        let state = ScanState {
+            scanner: self,
+            next_height: None,
+            latest_height: 0,
+            scanned: None,
+            queue: VecDeque::new(),
+        };
+        stream::unfold(state, |mut state| async move {
+            let item = state.next().await;
+            Some((item, state))
+        })
+    }
+}
+
+struct ScanState<C, K> {
+    scanner: ContractScanner<C, K>,
+    next_height: Option<u64>,
+    latest_height: u64,
+    /// Height whose events are queued, and which is checkpointed once they are drained
+    scanned: Option<u64>,
+    queue: VecDeque<ContractEvent>,
+}
+
+impl<C, K> ScanState<C, K>
+where
+    C: Client + Send + Sync,
+    K: Checkpoint,
+{
+    async fn next(&mut self) -> Result<ContractEvent, ChainError> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Ok(event);
+            }
+
+            if let Some(height) = self.scanned {
+                self.scanner.checkpoint.save(height).await?;
+                self.scanned = None;
+            }
+
+            let height = match self.next_height {
+                Some(height) => height,
+                None => {
+                    let height = match self.scanner.checkpoint.load().await? {
+                        Some(height) => height + 1,
+                        None => self.scanner.start_height,
+                    };
+                    self.next_height = Some(height);
+                    height
+                }
+            };
+
+            if height > self.latest_height {
+                self.latest_height = self
+                    .scanner
+                    .client
+                    .status()
+                    .await?
+                    .sync_info
+                    .latest_block_height
+                    .value();
+                if height > self.latest_height {
+                    tokio::time::sleep(self.scanner.poll_interval).await;
+                    continue;
+                }
+            }
+
+            let events = self.scanner.scan_height(height).await?;
+            self.queue.extend(events);
+            self.scanned = Some(height);
+            self.next_height = Some(height + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: &str, attributes: &[(&str, &str)]) -> Event {
+        Event {
+            kind: kind.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| EventAttribute {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    index: true,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn filters_wasm_events_by_contract_and_action() {
+        let contract = Address::new("juno", &[1; 32]).unwrap();
+        let other = Address::new("juno", &[2; 32]).unwrap();
+        let sender = Address::new("juno", &[3; 20]).unwrap();
+
+        let events = vec![
+            event(
+                "message",
+                &[
+                    ("action", "/cosmwasm.wasm.v1.MsgExecuteContract"),
+                    ("sender", sender.as_ref()),
+                ],
+            ),
+            event("execute", &[("_contract_address", contract.as_ref())]),
+            event(
+                "wasm",
+                &[("_contract_address", contract.as_ref()), ("action", "swap")],
+            ),
+            event(
+                "wasm-pool",
+                &[("_contract_address", contract.as_ref()), ("action", "sync")],
+            ),
+            event(
+                "wasm",
+                &[("_contract_address", other.as_ref()), ("action", "swap")],
+            ),
+        ];
+
+        let filter = ScanFilter::new(vec![contract.clone()]);
+        let found = filter.contract_events(7, Hash::None, &events);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].kind, "wasm");
+        assert_eq!(found[0].contract, contract);
+        assert_eq!(found[0].sender, Some(sender));
+        assert_eq!(found[1].kind, "wasm-pool");
+
+        let filter = filter.with_actions(vec!["sync".to_string()]);
+        let found = filter.contract_events(7, Hash::None, &events);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].action.as_deref(), Some("sync"));
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+
+use crate::chain::error::ChainError;
+use crate::chain::msg::IntoAny;
+use crate::chain::request::TxOptions;
+use crate::chain::tx::RawTx;
+use crate::config::cfg::ChainConfig;
+use crate::modules::auth::error::AccountError;
+use crate::modules::auth::model::{Account, Address};
+use crate::signing_key::signer::ToSigner;
+
+use super::client::{ClientAbciQuery, ClientTxCommit, ClientTxSync};
+
+const SEQUENCE_MISMATCH: &str = "account sequence mismatch, expected ";
+
+/// `ErrWrongSequence` of the cosmos-sdk
+const CODE_WRONG_SEQUENCE: u32 = 32;
+
+/// Hands out account sequences locally, so several txs of one key can be in flight at once.
+///
+/// The account of an address is queried once, after that every tx gets the next sequence
+/// without waiting for the previous one to be included. Clones share their sequences,
+/// so one manager can be handed to every task sending with the same keys.
+/// When the chain rejects a tx with an account sequence mismatch (code 32), the sequence
+/// the chain expects is taken from the log and the tx is signed and broadcast again,
+/// `max_retries` times at most, once by default. Sequences still reserved by other txs are
+/// never handed out twice, a tx that is ahead of the chain waits `retry_delay` for them instead.
+#[derive(Clone, Debug)]
+pub struct SequenceManager {
+    accounts: Arc<Mutex<HashMap<String, Sequences>>>,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl Default for SequenceManager {
+    fn default() -> Self {
+        Self {
+            accounts: Arc::default(),
+            max_retries: 1,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The sequences of one account
+#[derive(Debug)]
+struct Sequences {
+    /// `account.sequence` is the next sequence that was never handed out
+    account: Account,
+    /// handed out and not yet broadcast or released
+    reserved: BTreeSet<u64>,
+    /// released before their tx was broadcast, handed out again before new ones
+    released: BTreeSet<u64>,
+}
+
+impl Sequences {
+    fn reserve(&mut self) -> Account {
+        let sequence = self.released.pop_first().unwrap_or_else(|| {
+            self.account.sequence += 1;
+            self.account.sequence - 1
+        });
+        self.reserved.insert(sequence);
+
+        Account {
+            sequence,
+            ..self.account.clone()
+        }
+    }
+
+    fn release(&mut self, sequence: u64) {
+        if self.reserved.remove(&sequence) {
+            self.released.insert(sequence);
+        }
+    }
+
+    // the chain expects `expected` next, but reserved sequences above it may still be on their way
+    fn resync(&mut self, expected: u64) {
+        self.reserved.retain(|s| *s >= expected);
+        let next = self
+            .reserved
+            .last()
+            .map_or(expected, |last| expected.max(last + 1));
+        self.released.retain(|s| (expected..next).contains(s));
+        self.account.sequence = next;
+    }
+}
+
+impl SequenceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often [`SequenceManager::send`] signs a tx again after a sequence mismatch
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// How long a tx that is ahead of the chain waits for the txs before it
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// The account of `address` with the next free sequence, which is reserved for the caller
+    /// until it is passed to [`SequenceManager::confirm`] or [`SequenceManager::release`]
+    pub async fn next_account<C>(
+        &self,
+        client: &C,
+        address: &Address,
+    ) -> Result<Account, AccountError>
+    where
+        C: ClientAbciQuery + Sync,
+    {
+        let mut accounts = self.accounts.lock().await;
+        if !accounts.contains_key(address.as_ref()) {
+            let account = client.auth_query_account(address.clone()).await?.account;
+            accounts.insert(
+                address.to_string(),
+                Sequences {
+                    account,
+                    reserved: BTreeSet::new(),
+                    released: BTreeSet::new(),
+                },
+            );
+        }
+
+        Ok(accounts
+            .get_mut(address.as_ref())
+            .expect("account was inserted above")
+            .reserve())
+    }
+
+    /// Marks a sequence from [`SequenceManager::next_account`] as used by a broadcast tx
+    pub async fn confirm(&self, address: &Address, sequence: u64) {
+        if let Some(sequences) = self.accounts.lock().await.get_mut(address.as_ref()) {
+            sequences.reserved.remove(&sequence);
+        }
+    }
+
+    /// Gives back a sequence from [`SequenceManager::next_account`] whose tx was never broadcast,
+    /// or was rejected by the chain, so the next tx takes it instead of leaving a gap
+    pub async fn release(&self, address: &Address, sequence: u64) {
+        if let Some(sequences) = self.accounts.lock().await.get_mut(address.as_ref()) {
+            sequences.release(sequence);
+        }
+    }
+
+    /// Continues handing out sequences of `address` from `sequence`, the one the chain expects.
+    /// Sequences that are still reserved are not handed out again, so this never goes below them.
+    pub async fn resync(&self, address: &Address, sequence: u64) {
+        if let Some(sequences) = self.accounts.lock().await.get_mut(address.as_ref()) {
+            sequences.resync(sequence);
+        }
+    }
+
+    /// Forgets `address`, so its account is queried again before its next tx
+    pub async fn reset(&self, address: &Address) {
+        self.accounts.lock().await.remove(address.as_ref());
+    }
+
+    /// Signs `msgs` with the next sequence of `key` and broadcasts them with `broadcast`.
+    /// `tx_options.account` is ignored.
+    pub async fn send<C, T, R, F, Fut>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        msgs: Vec<T>,
+        key: &dyn ToSigner,
+        tx_options: &TxOptions,
+        broadcast: F,
+    ) -> Result<R, AccountError>
+    where
+        C: ClientAbciQuery + Sync,
+        T: IntoAny + Clone + Send + Sync,
+        <T as IntoAny>::Err: Send + Sync,
+        F: Fn(RawTx) -> Fut,
+        Fut: Future<Output = Result<R, ChainError>>,
+    {
+        let address = key.to_chain_addr(chain_cfg).await?;
+
+        let mut retries = 0;
+        loop {
+            let account = self.next_account(client, &address).await?;
+            let sequence = account.sequence;
+            let tx_options = TxOptions {
+                account: Some(account),
+                ..tx_options.clone()
+            };
+
+            let (res, broadcasted) = match client
+                .tx_sign(chain_cfg, msgs.clone(), key, &tx_options)
+                .await
+            {
+                Ok(raw) => (broadcast(raw).await.map_err(AccountError::from), true),
+                Err(e) => (Err(e), false),
+            };
+
+            let (expected, used) = match &res {
+                Ok(_) => (None, true),
+                Err(AccountError::ChainError(e)) => (expected_sequence(e), used_sequence(e)),
+                Err(_) => (None, false),
+            };
+            // only a tx that passed CheckTx uses up its sequence
+            if broadcasted && used {
+                self.confirm(&address, sequence).await;
+            } else {
+                self.release(&address, sequence).await;
+            }
+
+            let Some(expected) = expected.filter(|_| retries < self.max_retries) else {
+                return res;
+            };
+            retries += 1;
+
+            let waiting = match self.accounts.lock().await.get_mut(address.as_ref()) {
+                Some(sequences) => {
+                    sequences.resync(expected);
+                    sequences
+                        .reserved
+                        .range(expected..sequence)
+                        .next()
+                        .is_some()
+                }
+                None => false,
+            };
+            // the txs with the sequences before this one have not reached the chain yet
+            if waiting {
+                tokio::time::sleep(self.retry_delay).await;
+            }
+        }
+    }
+
+    /// [`SequenceManager::send`] with `broadcast_tx_commit`
+    pub async fn send_commit<C, T>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        msgs: Vec<T>,
+        key: &dyn ToSigner,
+        tx_options: &TxOptions,
+    ) -> Result<<C as ClientTxCommit>::Response, AccountError>
+    where
+        C: ClientTxCommit + ClientAbciQuery + Sync,
+        T: IntoAny + Clone + Send + Sync,
+        <T as IntoAny>::Err: Send + Sync,
+    {
+        self.send(client, chain_cfg, msgs, key, tx_options, |raw| async move {
+            client.broadcast_tx_commit(&raw).await
+        })
+        .await
+    }
+
+    /// [`SequenceManager::send`] with `broadcast_tx_sync`
+    pub async fn send_sync<C, T>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        msgs: Vec<T>,
+        key: &dyn ToSigner,
+        tx_options: &TxOptions,
+    ) -> Result<<C as ClientTxSync>::Response, AccountError>
+    where
+        C: ClientTxSync + ClientAbciQuery + Sync,
+        T: IntoAny + Clone + Send + Sync,
+        <T as IntoAny>::Err: Send + Sync,
+    {
+        self.send(client, chain_cfg, msgs, key, tx_options, |raw| async move {
+            client.broadcast_tx_sync(&raw).await
+        })
+        .await
+    }
+}
+
+/// The sequence the chain expects when `e` is an account sequence mismatch (code 32),
+/// from a log like `account sequence mismatch, expected 5, got 4: incorrect account sequence`
+pub fn expected_sequence(e: &ChainError) -> Option<u64> {
+    let log = match e {
+        ChainError::AbciQuery { res } if res.code.value() == CODE_WRONG_SEQUENCE => &res.log,
+        ChainError::TxCommit {
+            code,
+            log,
+            delivered: false,
+            ..
+        }
+        | ChainError::TxSync { code, log, .. }
+        | ChainError::TxAsync { code, log, .. }
+            if *code == CODE_WRONG_SEQUENCE =>
+        {
+            log
+        }
+        _ => return None,
+    };
+
+    let start = log.find(SEQUENCE_MISMATCH)? + SEQUENCE_MISMATCH.len();
+    let digits: String = log[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+// a tx that failed in DeliverTx was included in a block, every other failure leaves the sequence
+fn used_sequence(e: &ChainError) -> bool {
+    matches!(
+        e,
+        ChainError::TxCommit {
+            delivered: true,
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_expected_sequence() {
+        let mismatch = |code| ChainError::TxSync {
+            res: String::new(),
+            code,
+            log: "account sequence mismatch, expected 17, got 15: incorrect account sequence"
+                .to_string(),
+        };
+        assert_eq!(expected_sequence(&mismatch(32)), Some(17));
+        // only code 32 is a sequence mismatch, whatever the log says
+        assert_eq!(expected_sequence(&mismatch(5)), None);
+
+        let e = ChainError::TxSync {
+            res: String::new(),
+            code: 5,
+            log: "insufficient funds".to_string(),
+        };
+        assert_eq!(expected_sequence(&e), None);
+        assert!(!used_sequence(&e));
+
+        let e = ChainError::TxCommit {
+            res: String::new(),
+            code: 5,
+            log: "insufficient funds".to_string(),
+            delivered: true,
+        };
+        assert_eq!(expected_sequence(&e), None);
+        assert!(used_sequence(&e));
+    }
+
+    #[test]
+    fn resync_keeps_reserved_sequences() {
+        let mut sequences = Sequences {
+            account: Account {
+                address: Address::new("juno", &[1; 20]).unwrap(),
+                pubkey: None,
+                account_number: 1,
+                sequence: 4,
+            },
+            reserved: BTreeSet::new(),
+            released: BTreeSet::new(),
+        };
+        let reserved: Vec<_> = (0..3).map(|_| sequences.reserve().sequence).collect();
+        assert_eq!(reserved, vec![4, 5, 6]);
+
+        // a tx that was never broadcast leaves no gap
+        sequences.release(5);
+        assert_eq!(sequences.reserve().sequence, 5);
+        sequences.release(6);
+
+        // the chain has not seen 4 and 5 yet, which are still on their way
+        sequences.resync(4);
+        assert_eq!(sequences.reserve().sequence, 6);
+        assert_eq!(sequences.reserve().sequence, 7);
+
+        // txs sent around the manager moved the chain past every reservation
+        sequences.resync(10);
+        assert!(sequences.reserved.is_empty());
+        assert_eq!(sequences.reserve().sequence, 10);
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn resyncs_after_mismatch() {
+        use crate::chain::fee::Fee;
+        use crate::clients::fake::FakeChain;
+        use crate::modules::bank::api::BankTxCommit;
+
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+        let sequences = SequenceManager::new();
+        let with_fee = TxOptions {
+            fee: Some(Fee::new(FakeChain::coin(50_000), 200_000u64, None, None)),
+            ..Default::default()
+        };
+
+        for tx_options in [TxOptions::default(), with_fee] {
+            sequences
+                .send_commit(&chain, &cfg, vec![req.clone()], &key, &tx_options)
+                .await
+                .unwrap();
+
+            // a tx sent without the manager leaves its next sequence stale,
+            // which is caught by the simulation or by the broadcast when the fee is given
+            chain
+                .bank_send_commit(&cfg, req.clone(), &key, &TxOptions::default())
+                .await
+                .unwrap();
+            sequences
+                .send_commit(&chain, &cfg, vec![req.clone()], &key, &tx_options)
+                .await
+                .unwrap();
+        }
+        assert_eq!(chain.account(&sender).unwrap().sequence, 6);
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn only_txs_past_check_tx_use_their_sequence() {
+        use crate::chain::fee::Fee;
+        use crate::clients::fake::FakeChain;
+        use crate::modules::bank::model::SendRequest;
+
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+        // any sequence that is handed out twice or skipped fails the send
+        let sequences = SequenceManager::new().with_max_retries(0);
+        let fee = |amount| TxOptions {
+            fee: Some(Fee::new(FakeChain::coin(amount), 200_000u64, None, None)),
+            ..Default::default()
+        };
+
+        // CheckTx rejects a fee the sender cannot pay
+        let res = sequences
+            .send_commit(&chain, &cfg, vec![req.clone()], &key, &fee(10_000_000))
+            .await;
+        assert!(matches!(
+            res,
+            Err(AccountError::ChainError(ChainError::TxCommit {
+                delivered: false,
+                ..
+            }))
+        ));
+        assert_eq!(
+            sequences.accounts.lock().await[sender.as_ref()].released,
+            BTreeSet::from([0])
+        );
+        sequences
+            .send_commit(&chain, &cfg, vec![req.clone()], &key, &fee(50_000))
+            .await
+            .unwrap();
+
+        // a send of more than the balance fails in DeliverTx, after the sequence was used
+        let too_much = SendRequest {
+            amounts: vec![FakeChain::coin(10_000_000)],
+            ..req.clone()
+        };
+        let res = sequences
+            .send_commit(&chain, &cfg, vec![too_much], &key, &fee(50_000))
+            .await;
+        assert!(matches!(
+            res,
+            Err(AccountError::ChainError(ChainError::TxCommit {
+                delivered: true,
+                ..
+            }))
+        ));
+        sequences
+            .send_commit(&chain, &cfg, vec![req], &key, &fee(50_000))
+            .await
+            .unwrap();
+        assert_eq!(chain.account(&sender).unwrap().sequence, 3);
+    }
+
+    // every tx waits before its broadcast, so the later ones are signed and simulated
+    // while the sequences before them are still reserved
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn concurrent_sends() {
+        use futures::future::join_all;
+
+        use crate::clients::client::ClientTxCommit;
+        use crate::clients::fake::FakeChain;
+
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+        // a tx may be ahead of the chain more than once before the ones before it are in
+        let sequences = SequenceManager::new()
+            .with_max_retries(5)
+            .with_retry_delay(Duration::from_millis(1));
+        let tx_options = TxOptions::default();
+
+        let results = join_all((0..4).map(|_| {
+            sequences.send(&chain, &cfg, vec![req.clone()], &key, &tx_options, |raw| {
+                let chain = &chain;
+                async move {
+                    tokio::task::yield_now().await;
+                    chain.broadcast_tx_commit(&raw).await
+                }
+            })
+        }))
+        .await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(chain.account(&sender).unwrap().sequence, 4);
+
+        let results =
+            join_all((0..4).map(|_| {
+                sequences.send_commit(&chain, &cfg, vec![req.clone()], &key, &tx_options)
+            }))
+            .await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(chain.account(&sender).unwrap().sequence, 8);
+        assert!(sequences.accounts.lock().await[sender.as_ref()]
+            .reserved
+            .is_empty());
+    }
+}
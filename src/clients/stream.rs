@@ -0,0 +1,935 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use cosmrs::tendermint::{
+    abci::{response::DeliverTx, Event},
+    block::{Block, Header, Height},
+    Hash, Time,
+};
+use cosmrs::Tx;
+use futures::{stream, Stream, StreamExt};
+use log::warn;
+use sha2::{Digest, Sha256};
+use tendermint_rpc::{
+    client::CompatMode,
+    endpoint::{block_results, tx},
+    event::{EventData, TxInfo},
+    query::{EventType, Query},
+    Client, Order, Subscription, SubscriptionClient, WebSocketClient, WebSocketClientUrl,
+};
+use tokio::task::JoinHandle;
+
+use crate::chain::error::ChainError;
+
+use super::tendermint_rpc::parse_gas;
+
+const TX_SEARCH_PER_PAGE: u8 = 100;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// First height to yield. Heights between it and the chain tip are backfilled.
+    /// When `None`, the stream starts at the first event received.
+    pub start_height: Option<u64>,
+
+    /// How long to wait before reconnecting after the websocket driver dies.
+    /// Doubles after every failed attempt in a row, up to `max_reconnect_delay`.
+    pub reconnect_delay: Duration,
+
+    /// Upper bound of the reconnect delay
+    pub max_reconnect_delay: Duration,
+
+    /// Failed reconnect attempts in a row after which the stream yields the error and ends.
+    /// When `None`, it retries forever.
+    pub max_retries: Option<u32>,
+
+    /// Compat mode for the websocket client, defaults to the latest supported version
+    pub compat_mode: Option<CompatMode>,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            start_height: None,
+            reconnect_delay: Duration::from_secs(3),
+            max_reconnect_delay: Duration::from_secs(60),
+            max_retries: Some(10),
+            compat_mode: None,
+        }
+    }
+}
+
+impl StreamOptions {
+    /// Delay before the next attempt after `failures` failed attempts in a row
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.reconnect_delay
+            .saturating_mul(factor)
+            .min(self.max_reconnect_delay)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NewBlock {
+    pub height: u64,
+    pub time: Time,
+    pub header: Header,
+    pub txs: Vec<Tx>,
+    pub tx_results: Vec<DeliverTx>,
+    /// Begin and end block events
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewBlockHeader {
+    pub height: u64,
+    pub time: Time,
+    pub header: Header,
+}
+
+#[derive(Clone, Debug)]
+pub struct TxEvent {
+    pub hash: Hash,
+    pub height: u64,
+    /// Position of the tx in its block, `None` when the node does not report it
+    pub index: Option<u32>,
+    pub tx: Tx,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    pub log: String,
+    pub events: Vec<Event>,
+}
+
+/// Streams every new block with its decoded txs and their results.
+/// Reconnects after the websocket driver dies, and backfills the missed heights via `block_results`.
+/// Ends after yielding the error once reconnecting, or fetching a block, fails `max_retries` times in a row.
+pub fn new_blocks(
+    url: &str,
+    opts: StreamOptions,
+) -> impl Stream<Item = Result<NewBlock, ChainError>> {
+    follow_blocks(BlockFollower::new(url, opts))
+}
+
+/// Streams the header of every new block.
+/// Reconnects after the websocket driver dies, and backfills the missed heights.
+/// Ends after yielding the error once reconnecting, or fetching a block, fails `max_retries` times in a row.
+pub fn new_block_headers(
+    url: &str,
+    opts: StreamOptions,
+) -> impl Stream<Item = Result<NewBlockHeader, ChainError>> {
+    follow_block_headers(BlockFollower::new(url, opts))
+}
+
+fn follow_blocks<S: BlockSource>(
+    follower: BlockFollower<S>,
+) -> impl Stream<Item = Result<NewBlock, ChainError>> {
+    stream::unfold(Some(follower), |follower| async move {
+        let mut follower = follower?;
+        loop {
+            let (height, block) = match follower.peek().await {
+                Ok(next) => next,
+                Err(e) => return Some((Err(e), None)),
+            };
+            if let Err(e) = follower.source.ensure_connected().await {
+                return Some((Err(e), None));
+            }
+            match follower.fetch_new_block(height, block).await {
+                Ok((block, results)) => {
+                    follower.advance();
+                    return Some((decode_new_block(block, results), Some(follower)));
+                }
+                Err(e) => {
+                    if let Err(e) = follower.fetch_failed(e).await {
+                        return Some((Err(e), None));
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn follow_block_headers<S: BlockSource>(
+    follower: BlockFollower<S>,
+) -> impl Stream<Item = Result<NewBlockHeader, ChainError>> {
+    stream::unfold(Some(follower), |follower| async move {
+        let mut follower = follower?;
+        loop {
+            let header = match follower.peek().await {
+                Ok((_, Some(block))) => Ok(block.header),
+                Ok((height, None)) => match follower.source.ensure_connected().await {
+                    Ok(_) => follower
+                        .source
+                        .block(height)
+                        .await
+                        .map(|block| block.header),
+                    Err(e) => return Some((Err(e), None)),
+                },
+                Err(e) => return Some((Err(e), None)),
+            };
+            match header {
+                Ok(header) => {
+                    follower.advance();
+                    let item = NewBlockHeader {
+                        height: header.height.value(),
+                        time: header.time,
+                        header,
+                    };
+                    return Some((Ok(item), Some(follower)));
+                }
+                Err(e) => {
+                    if let Err(e) = follower.fetch_failed(e).await {
+                        return Some((Err(e), None));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Streams every tx matching `query`, which must be a `tm.event = 'Tx'` query.
+/// After reconnecting, txs committed while disconnected are backfilled via `tx_search`.
+/// Ends after yielding the error once reconnecting fails `max_retries` times in a row.
+pub fn txs(
+    url: &str,
+    query: Query,
+    opts: StreamOptions,
+) -> impl Stream<Item = Result<TxEvent, ChainError>> {
+    stream::unfold(
+        Some(TxFollower::new(url, query, opts)),
+        |follower| async move {
+            let mut follower = follower?;
+            match follower.next().await {
+                Ok(raw) => Some((decode_tx_event(raw), Some(follower))),
+                Err(e) => Some((Err(e), None)),
+            }
+        },
+    )
+}
+
+struct Connection {
+    client: WebSocketClient,
+    subscription: Subscription,
+    driver: JoinHandle<Result<(), tendermint_rpc::Error>>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.driver.abort();
+    }
+}
+
+/// A websocket subscription that is re-established whenever it fails
+struct Reconnecting {
+    url: String,
+    query: Query,
+    opts: StreamOptions,
+    conn: Option<Connection>,
+}
+
+impl Reconnecting {
+    fn new(url: &str, query: Query, opts: StreamOptions) -> Self {
+        Self {
+            url: url.to_string(),
+            query,
+            opts,
+            conn: None,
+        }
+    }
+
+    async fn connect(&self, url: WebSocketClientUrl) -> Result<Connection, ChainError> {
+        let mut builder = WebSocketClient::builder(url);
+        if let Some(compat_mode) = self.opts.compat_mode {
+            builder = builder.compat_mode(compat_mode);
+        }
+        let (client, driver) = builder.build().await?;
+        let driver = tokio::spawn(async move { driver.run().await });
+
+        match client.subscribe(self.query.clone()).await {
+            Ok(subscription) => Ok(Connection {
+                client,
+                subscription,
+                driver,
+            }),
+            Err(e) => {
+                driver.abort();
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Returns true if a new connection had to be established.
+    /// Fails right away on an invalid url, and after `max_retries` failed attempts in a row otherwise.
+    async fn ensure_connected(&mut self) -> Result<bool, ChainError> {
+        if self.conn.is_some() {
+            return Ok(false);
+        }
+
+        let url: WebSocketClientUrl = self.url.parse()?;
+        let mut failures = 0;
+        loop {
+            match self.connect(url.clone()).await {
+                Ok(conn) => {
+                    self.conn = Some(conn);
+                    return Ok(true);
+                }
+                Err(e) => {
+                    failures += 1;
+                    if matches!(self.opts.max_retries, Some(max) if failures > max) {
+                        return Err(e);
+                    }
+                    let delay = self.opts.backoff(failures);
+                    warn!(
+                        "failed to subscribe to {} on {}, retrying in {:?}: {}",
+                        self.query, self.url, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    async fn client(&mut self) -> Result<&WebSocketClient, ChainError> {
+        self.ensure_connected().await?;
+        Ok(&self
+            .conn
+            .as_ref()
+            .expect("connection was just established")
+            .client)
+    }
+
+    /// Waits for the next event of an established connection
+    async fn next_event(&mut self) -> Result<EventData, ChainError> {
+        let closed = || ChainError::SubscriptionClosed {
+            query: self.query.to_string(),
+        };
+        let conn = self.conn.as_mut().ok_or_else(closed)?;
+        match conn.subscription.next().await {
+            Some(event) => Ok(event?.data),
+            None => Err(closed()),
+        }
+    }
+
+    async fn disconnect(&mut self, e: ChainError) {
+        warn!(
+            "subscription to {} on {} failed, reconnecting: {}",
+            self.query, self.url, e
+        );
+        self.conn = None;
+        tokio::time::sleep(self.opts.reconnect_delay).await;
+    }
+}
+
+/// Where a [`BlockFollower`] gets its blocks from
+#[async_trait]
+trait BlockSource: Send {
+    /// Returns true if a new subscription had to be established
+    async fn ensure_connected(&mut self) -> Result<bool, ChainError>;
+
+    /// The block of the next `NewBlock` event, `None` for any other event
+    async fn next_block(&mut self) -> Result<Option<Block>, ChainError>;
+
+    async fn disconnect(&mut self, e: ChainError);
+
+    /// Fetches a block over the established connection
+    async fn block(&mut self, height: u64) -> Result<Block, ChainError>;
+
+    /// Fetches the results of a block over the established connection
+    async fn block_results(&mut self, height: u64) -> Result<block_results::Response, ChainError>;
+}
+
+#[async_trait]
+impl BlockSource for Reconnecting {
+    async fn ensure_connected(&mut self) -> Result<bool, ChainError> {
+        Reconnecting::ensure_connected(self).await
+    }
+
+    async fn next_block(&mut self) -> Result<Option<Block>, ChainError> {
+        match self.next_event().await? {
+            EventData::NewBlock {
+                block: Some(block), ..
+            } => Ok(Some(block)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn disconnect(&mut self, e: ChainError) {
+        Reconnecting::disconnect(self, e).await
+    }
+
+    async fn block(&mut self, height: u64) -> Result<Block, ChainError> {
+        let height = Height::try_from(height)?;
+        Ok(self.client().await?.block(height).await?.block)
+    }
+
+    async fn block_results(&mut self, height: u64) -> Result<block_results::Response, ChainError> {
+        let height = Height::try_from(height)?;
+        Ok(self.client().await?.block_results(height).await?)
+    }
+}
+
+/// Follows `NewBlock` events and hands out every height in order exactly once,
+/// along with the event's block when it is the one being handed out.
+struct BlockFollower<S = Reconnecting> {
+    source: S,
+    next_height: Option<u64>,
+    latest: Option<Block>,
+    max_retries: Option<u32>,
+    /// Failed fetches in a row, a fetch that keeps failing ends the stream like a reconnect would
+    fetch_failures: u32,
+}
+
+impl BlockFollower {
+    fn new(url: &str, opts: StreamOptions) -> Self {
+        let (start_height, max_retries) = (opts.start_height, opts.max_retries);
+        Self::with_source(
+            Reconnecting::new(url, Query::from(EventType::NewBlock), opts),
+            start_height,
+            max_retries,
+        )
+    }
+}
+
+impl<S: BlockSource> BlockFollower<S> {
+    fn with_source(source: S, start_height: Option<u64>, max_retries: Option<u32>) -> Self {
+        Self {
+            source,
+            next_height: start_height,
+            latest: None,
+            max_retries,
+            fetch_failures: 0,
+        }
+    }
+
+    /// Returns the next height to yield, without consuming it
+    async fn peek(&mut self) -> Result<(u64, Option<Block>), ChainError> {
+        loop {
+            if let (Some(next), Some(latest)) = (self.next_height, &self.latest) {
+                let latest_height = latest.header.height.value();
+                if next < latest_height {
+                    return Ok((next, None));
+                }
+                if next == latest_height {
+                    return Ok((next, Some(latest.clone())));
+                }
+            }
+
+            self.source.ensure_connected().await?;
+            match self.source.next_block().await {
+                Ok(Some(block)) => {
+                    self.next_height.get_or_insert(block.header.height.value());
+                    self.latest = Some(block);
+                }
+                Ok(None) => {}
+                Err(e) => self.source.disconnect(e).await,
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        self.next_height = self.next_height.map(|height| height + 1);
+        self.fetch_failures = 0;
+    }
+
+    async fn fetch_new_block(
+        &mut self,
+        height: u64,
+        block: Option<Block>,
+    ) -> Result<(Block, block_results::Response), ChainError> {
+        let block = match block {
+            Some(block) => block,
+            None => self.source.block(height).await?,
+        };
+        let results = self.source.block_results(height).await?;
+        Ok((block, results))
+    }
+
+    /// Reconnects after a failed fetch, or gives back the error after `max_retries` in a row
+    async fn fetch_failed(&mut self, e: ChainError) -> Result<(), ChainError> {
+        self.fetch_failures += 1;
+        if matches!(self.max_retries, Some(max) if self.fetch_failures > max) {
+            return Err(e);
+        }
+        self.source.disconnect(e).await;
+        Ok(())
+    }
+}
+
+fn decode_new_block(
+    block: Block,
+    results: block_results::Response,
+) -> Result<NewBlock, ChainError> {
+    let txs = block
+        .data
+        .iter()
+        .map(|bytes| Tx::from_bytes(bytes).map_err(ChainError::proto_decoding))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let events = results
+        .begin_block_events
+        .unwrap_or_default()
+        .into_iter()
+        .chain(results.end_block_events.unwrap_or_default())
+        .collect();
+
+    Ok(NewBlock {
+        height: block.header.height.value(),
+        time: block.header.time,
+        header: block.header,
+        txs,
+        tx_results: results.txs_results.unwrap_or_default(),
+        events,
+    })
+}
+
+/// Hash of the raw tx bytes, as used by `tx_search`
+pub(crate) fn tx_hash(tx: &[u8]) -> Hash {
+    Hash::Sha256(Sha256::digest(tx).into())
+}
+
+/// A tx as received from either the subscription or `tx_search`, before decoding
+#[derive(Clone, Debug)]
+struct PendingTx {
+    hash: Hash,
+    height: u64,
+    index: Option<u32>,
+    tx: Vec<u8>,
+    gas_wanted: u64,
+    gas_used: u64,
+    log: String,
+    events: Vec<Event>,
+}
+
+impl From<TxInfo> for PendingTx {
+    fn from(info: TxInfo) -> Self {
+        Self {
+            hash: tx_hash(&info.tx),
+            height: info.height as u64,
+            index: info.index,
+            tx: info.tx,
+            gas_wanted: parse_gas(info.result.gas_wanted),
+            gas_used: parse_gas(info.result.gas_used),
+            log: info.result.log.unwrap_or_default(),
+            events: info.result.events,
+        }
+    }
+}
+
+impl From<tx::Response> for PendingTx {
+    fn from(res: tx::Response) -> Self {
+        Self {
+            hash: res.hash,
+            height: res.height.value(),
+            index: Some(res.index),
+            tx: res.tx,
+            gas_wanted: res.tx_result.gas_wanted as u64,
+            gas_used: res.tx_result.gas_used as u64,
+            log: res.tx_result.log,
+            events: res.tx_result.events,
+        }
+    }
+}
+
+/// Where a [`TxFollower`] gets its txs from
+#[async_trait]
+trait TxSource: Send {
+    /// Returns true if a new subscription had to be established
+    async fn ensure_connected(&mut self) -> Result<bool, ChainError>;
+
+    /// The next tx of the subscription, `None` for any other event
+    async fn next_tx(&mut self) -> Result<Option<PendingTx>, ChainError>;
+
+    async fn disconnect(&mut self, e: ChainError);
+
+    async fn latest_height(&mut self) -> Result<u64, ChainError>;
+
+    /// One page of the matching txs from `from` on, in ascending order, with the total count
+    async fn search_txs(
+        &mut self,
+        from: u64,
+        page: u32,
+    ) -> Result<(Vec<PendingTx>, u32), ChainError>;
+}
+
+#[async_trait]
+impl TxSource for Reconnecting {
+    async fn ensure_connected(&mut self) -> Result<bool, ChainError> {
+        Reconnecting::ensure_connected(self).await
+    }
+
+    async fn next_tx(&mut self) -> Result<Option<PendingTx>, ChainError> {
+        match self.next_event().await? {
+            EventData::Tx { tx_result } => Ok(Some(tx_result.into())),
+            _ => Ok(None),
+        }
+    }
+
+    async fn disconnect(&mut self, e: ChainError) {
+        Reconnecting::disconnect(self, e).await
+    }
+
+    async fn latest_height(&mut self) -> Result<u64, ChainError> {
+        let status = self.client().await?.status().await?;
+        Ok(status.sync_info.latest_block_height.value())
+    }
+
+    async fn search_txs(
+        &mut self,
+        from: u64,
+        page: u32,
+    ) -> Result<(Vec<PendingTx>, u32), ChainError> {
+        let query = self.query.clone().and_gte("tx.height", from);
+        let res = self
+            .client()
+            .await?
+            .tx_search(query, false, page, TX_SEARCH_PER_PAGE, Order::Ascending)
+            .await?;
+        Ok((
+            res.txs.into_iter().map(PendingTx::from).collect(),
+            res.total_count,
+        ))
+    }
+}
+
+/// A `tx_search` that is paged through as the stream is consumed
+struct Backfill {
+    from: u64,
+    page: u32,
+    fetched: usize,
+}
+
+/// Follows tx events, yielding every matching tx in height order exactly once
+struct TxFollower<S = Reconnecting> {
+    source: S,
+    /// Height to backfill from on the next (re)connect
+    backfill_from: Option<u64>,
+    backfill: Option<Backfill>,
+    /// Height of the last tx handed out
+    last_height: Option<u64>,
+    /// Hashes of the txs handed out at `last_height`. Nodes do not always report the index
+    /// of a tx in its block, so txs are told apart by hash rather than position.
+    seen: HashSet<Hash>,
+    pending: VecDeque<PendingTx>,
+}
+
+impl TxFollower {
+    fn new(url: &str, query: Query, opts: StreamOptions) -> Self {
+        let start_height = opts.start_height;
+        Self::with_source(Reconnecting::new(url, query, opts), start_height)
+    }
+}
+
+impl<S: TxSource> TxFollower<S> {
+    fn with_source(source: S, start_height: Option<u64>) -> Self {
+        Self {
+            source,
+            backfill_from: start_height,
+            backfill: None,
+            last_height: None,
+            seen: HashSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn is_new(&self, tx: &PendingTx) -> bool {
+        match self.last_height {
+            Some(height) if tx.height < height => false,
+            Some(height) if tx.height == height => !self.seen.contains(&tx.hash),
+            _ => true,
+        }
+    }
+
+    fn mark_seen(&mut self, tx: &PendingTx) {
+        if self.last_height != Some(tx.height) {
+            self.seen.clear();
+            self.last_height = Some(tx.height);
+        }
+        self.seen.insert(tx.hash);
+        self.backfill_from = Some(tx.height);
+    }
+
+    /// Fails only once reconnecting has been given up on
+    async fn next(&mut self) -> Result<PendingTx, ChainError> {
+        loop {
+            if let Some(tx) = self.pending.pop_front() {
+                if self.is_new(&tx) {
+                    self.mark_seen(&tx);
+                    return Ok(tx);
+                }
+                continue;
+            }
+
+            // the subscription only sees txs committed after it was registered
+            if self.source.ensure_connected().await? {
+                match self.start_backfill().await {
+                    Ok(backfill) => self.backfill = Some(backfill),
+                    Err(e) => {
+                        self.source.disconnect(e).await;
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(backfill) = self.backfill.take() {
+                match self.source.search_txs(backfill.from, backfill.page).await {
+                    Ok((txs, total)) => {
+                        let fetched = backfill.fetched + txs.len();
+                        if !txs.is_empty() && fetched < total as usize {
+                            self.backfill = Some(Backfill {
+                                page: backfill.page + 1,
+                                fetched,
+                                ..backfill
+                            });
+                        }
+                        self.pending.extend(txs);
+                    }
+                    // reconnecting starts a new backfill from the last tx handed out
+                    Err(e) => self.source.disconnect(e).await,
+                }
+                continue;
+            }
+
+            match self.source.next_tx().await {
+                Ok(Some(tx)) => self.pending.push_back(tx),
+                Ok(None) => {}
+                Err(e) => self.source.disconnect(e).await,
+            }
+        }
+    }
+
+    async fn start_backfill(&mut self) -> Result<Backfill, ChainError> {
+        // remember where the first connection started, so a later reconnect can fill the gap
+        let from = match self.backfill_from {
+            Some(from) => from,
+            None => {
+                let height = self.source.latest_height().await?;
+                self.backfill_from = Some(height);
+                height
+            }
+        };
+        Ok(Backfill {
+            from,
+            page: 1,
+            fetched: 0,
+        })
+    }
+}
+
+fn decode_tx_event(tx: PendingTx) -> Result<TxEvent, ChainError> {
+    Ok(TxEvent {
+        hash: tx.hash,
+        height: tx.height,
+        index: tx.index,
+        tx: Tx::from_bytes(&tx.tx).map_err(ChainError::proto_decoding)?,
+        gas_wanted: tx.gas_wanted,
+        gas_used: tx.gas_used,
+        log: tx.log,
+        events: tx.events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_tx(height: u64, byte: u8, index: Option<u32>) -> PendingTx {
+        PendingTx {
+            hash: tx_hash(&[byte]),
+            height,
+            index,
+            tx: vec![byte],
+            gas_wanted: 0,
+            gas_used: 0,
+            log: String::new(),
+            events: vec![],
+        }
+    }
+
+    /// Serves `tx_search` pages of two txs from `chain`, and the live events of one entry of
+    /// `connections` per connection, which drops once its events run out
+    struct FakeSource {
+        chain: Vec<PendingTx>,
+        connections: VecDeque<VecDeque<PendingTx>>,
+        live: Option<VecDeque<PendingTx>>,
+        searches: Vec<(u64, u32)>,
+    }
+
+    #[async_trait]
+    impl TxSource for FakeSource {
+        async fn ensure_connected(&mut self) -> Result<bool, ChainError> {
+            if self.live.is_some() {
+                return Ok(false);
+            }
+            match self.connections.pop_front() {
+                Some(events) => {
+                    self.live = Some(events);
+                    Ok(true)
+                }
+                None => Err(ChainError::SubscriptionClosed {
+                    query: "gave up".to_string(),
+                }),
+            }
+        }
+
+        async fn next_tx(&mut self) -> Result<Option<PendingTx>, ChainError> {
+            match self.live.as_mut().and_then(|events| events.pop_front()) {
+                Some(tx) => Ok(Some(tx)),
+                None => Err(ChainError::SubscriptionClosed {
+                    query: "dropped".to_string(),
+                }),
+            }
+        }
+
+        async fn disconnect(&mut self, _e: ChainError) {
+            self.live = None;
+        }
+
+        async fn latest_height(&mut self) -> Result<u64, ChainError> {
+            Ok(self.chain.last().map_or(1, |tx| tx.height))
+        }
+
+        async fn search_txs(
+            &mut self,
+            from: u64,
+            page: u32,
+        ) -> Result<(Vec<PendingTx>, u32), ChainError> {
+            self.searches.push((from, page));
+            let matching: Vec<_> = self.chain.iter().filter(|tx| tx.height >= from).collect();
+            let txs = matching
+                .chunks(2)
+                .nth(page as usize - 1)
+                .unwrap_or_default()
+                .iter()
+                .map(|tx| (*tx).clone())
+                .collect();
+            Ok((txs, matching.len() as u32))
+        }
+    }
+
+    #[tokio::test]
+    async fn backfill_and_reconnect_yield_each_tx_once() {
+        let chain = vec![
+            pending_tx(5, 1, Some(0)),
+            pending_tx(5, 2, Some(1)),
+            pending_tx(6, 3, Some(0)),
+            pending_tx(7, 4, Some(0)),
+        ];
+        // live events carry no index, and overlap with what the backfills return
+        let connections = VecDeque::from([
+            VecDeque::from([pending_tx(6, 3, None)]),
+            VecDeque::from([pending_tx(7, 4, None), pending_tx(8, 5, None)]),
+        ]);
+        let source = FakeSource {
+            chain,
+            connections,
+            live: None,
+            searches: vec![],
+        };
+        let mut follower = TxFollower::with_source(source, Some(5));
+
+        let mut yielded = vec![];
+        for _ in 0..5 {
+            let tx = follower.next().await.unwrap();
+            yielded.push((tx.height, tx.tx[0]));
+        }
+        assert_eq!(yielded, vec![(5, 1), (5, 2), (6, 3), (7, 4), (8, 5)]);
+
+        // the first backfill is paged, the one after reconnecting starts at the last height
+        assert_eq!(follower.source.searches, vec![(5, 1), (5, 2), (7, 1)]);
+
+        // no connections left
+        assert!(follower.next().await.is_err());
+    }
+
+    fn test_block(height: u32) -> Block {
+        use cosmrs::tendermint::{account, block::header::Version, evidence, hash::AppHash};
+
+        let header = Header {
+            version: Version { block: 11, app: 0 },
+            chain_id: "test-1".parse().unwrap(),
+            height: Height::from(height),
+            time: Time::unix_epoch(),
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::None,
+            next_validators_hash: Hash::None,
+            consensus_hash: Hash::None,
+            app_hash: AppHash::default(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([0; 20]),
+        };
+        Block::new(header, vec![], evidence::List::default(), None).unwrap()
+    }
+
+    /// Sends one `NewBlock` event, but fails every fetch
+    struct FailingBlocks {
+        latest: Option<Block>,
+    }
+
+    #[async_trait]
+    impl BlockSource for FailingBlocks {
+        async fn ensure_connected(&mut self) -> Result<bool, ChainError> {
+            Ok(false)
+        }
+
+        async fn next_block(&mut self) -> Result<Option<Block>, ChainError> {
+            Ok(self.latest.take())
+        }
+
+        async fn disconnect(&mut self, _e: ChainError) {}
+
+        async fn block(&mut self, _height: u64) -> Result<Block, ChainError> {
+            unreachable!("the event carries the block")
+        }
+
+        async fn block_results(
+            &mut self,
+            _height: u64,
+        ) -> Result<block_results::Response, ChainError> {
+            Err(ChainError::SubscriptionClosed {
+                query: "block_results".to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_fetch_ends_the_stream() {
+        let source = FailingBlocks {
+            latest: Some(test_block(1)),
+        };
+        let follower = BlockFollower::with_source(source, None, Some(2));
+
+        // retried twice, then the error is yielded once and the stream ends
+        let blocks: Vec<_> = follow_blocks(follower).collect().await;
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].is_err());
+    }
+
+    #[test]
+    fn reconnect_backoff() {
+        let opts = StreamOptions {
+            reconnect_delay: Duration::from_secs(3),
+            max_reconnect_delay: Duration::from_secs(20),
+            ..Default::default()
+        };
+        assert_eq!(opts.backoff(1), Duration::from_secs(3));
+        assert_eq!(opts.backoff(2), Duration::from_secs(6));
+        assert_eq!(opts.backoff(3), Duration::from_secs(12));
+        assert_eq!(opts.backoff(4), Duration::from_secs(20));
+        assert_eq!(opts.backoff(40), Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    async fn invalid_url_is_not_retried() {
+        let mut ws = Reconnecting::new(
+            "not a url",
+            Query::from(EventType::NewBlock),
+            StreamOptions {
+                max_retries: None,
+                ..Default::default()
+            },
+        );
+        assert!(ws.ensure_connected().await.is_err());
+    }
+}
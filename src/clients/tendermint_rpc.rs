@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
 use async_trait::async_trait;
 use cosmrs::{
     rpc::Client,
@@ -6,9 +9,9 @@ use cosmrs::{
         Hash,
     },
 };
+use futures::StreamExt;
 use lazy_static::lazy_static;
-use log::info;
-use std::time::Duration;
+use log::{debug, info};
 use tendermint_rpc::{
     client::CompatMode,
     endpoint::{
@@ -16,17 +19,18 @@ use tendermint_rpc::{
         broadcast::{tx_async, tx_commit, tx_sync},
         tx,
     },
+    event::{self, EventData},
     query::{EventType, Query},
-    HttpClient, Order,
+    HttpClient, Order, Subscription, SubscriptionClient, WebSocketClient,
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, time::Instant};
 
 use crate::chain::error::ChainError;
 use crate::chain::tx::RawTx;
 
 use super::client::{
     ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync, GetErr, GetEvents, GetValue,
-    HashSearch,
+    HashSearch, TxInclusion, WaitForTx, WaitOptions,
 };
 
 impl GetEvents for tx_commit::Response {
@@ -43,9 +47,17 @@ impl GetEvents for DeliverTx {
 
 impl GetErr for tx_commit::Response {
     fn get_err(self) -> Result<Self, ChainError> {
-        if self.deliver_tx.code.is_err() || self.check_tx.code.is_err() {
+        let (code, log, delivered) = if self.check_tx.code.is_err() {
+            (self.check_tx.code, &self.check_tx.log, false)
+        } else {
+            (self.deliver_tx.code, &self.deliver_tx.log, true)
+        };
+        if code.is_err() {
             return Err(ChainError::TxCommit {
                 res: format!("{:?}", self),
+                code: code.value(),
+                log: log.clone(),
+                delivered,
             });
         }
         Ok(self)
@@ -57,6 +69,8 @@ impl GetErr for tx_sync::Response {
         if self.code.is_err() {
             return Err(ChainError::TxSync {
                 res: format!("{:?}", self),
+                code: self.code.value(),
+                log: self.log.clone(),
             });
         }
         Ok(self)
@@ -68,6 +82,8 @@ impl GetErr for tx_async::Response {
         if self.code.is_err() {
             return Err(ChainError::TxAsync {
                 res: format!("{:?}", self),
+                code: self.code.value(),
+                log: self.log.clone(),
             });
         }
         Ok(self)
@@ -118,23 +134,250 @@ impl<T> HashSearch for T
 where
     T: ClientAbciQuery + Client + Sync,
 {
-    async fn hash_search(&self, hash: &Hash) -> Result<tx::Response, ChainError> {
-        let query = Query::from(EventType::Tx).and_eq("tx.hash", hash.to_string());
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
-        let start_time = tokio::time::Instant::now();
+    async fn hash_search_with(
+        &self,
+        hash: &Hash,
+        opts: &WaitOptions,
+    ) -> Result<tx::Response, ChainError> {
+        poll_tx_search(self, hash, opts).await
+    }
+}
+
+fn tx_hash_query(hash: &Hash) -> Query {
+    Query::from(EventType::Tx).and_eq("tx.hash", hash.to_string())
+}
+
+/// The rpc calls that waiting for a tx makes besides the subscription
+#[async_trait]
+trait TxLookup {
+    async fn search_tx(&self, query: Query) -> Result<Option<tx::Response>, ChainError>;
+
+    async fn latest_height(&self) -> Result<u64, ChainError>;
+}
+
+#[async_trait]
+impl<C> TxLookup for C
+where
+    C: Client + Sync,
+{
+    async fn search_tx(&self, query: Query) -> Result<Option<tx::Response>, ChainError> {
+        let search_res = self
+            .tx_search(query, false, 1, 255, Order::Ascending)
+            .await?;
+        Ok(search_res.txs.into_iter().next())
+    }
+
+    async fn latest_height(&self) -> Result<u64, ChainError> {
+        Ok(self.status().await?.sync_info.latest_block_height.value())
+    }
+}
+
+async fn poll_tx_search<C>(
+    client: &C,
+    hash: &Hash,
+    opts: &WaitOptions,
+) -> Result<tx::Response, ChainError>
+where
+    C: TxLookup + Sync,
+{
+    let query = tx_hash_query(hash);
+    let deadline = Instant::now() + opts.timeout;
+    let mut height_deadline = HeightDeadline::default();
+    let mut interval = tokio::time::interval(opts.poll_interval);
+    interval.tick().await;
+    loop {
         interval.tick().await;
-        loop {
-            interval.tick().await;
-
-            let search_res = self
-                .tx_search(query.clone(), false, 1, 255, Order::Ascending)
-                .await?;
-            if let Some(tx) = search_res.txs.first() {
-                return Ok(tx.clone());
+
+        // the height is read before searching, so the search covers every block up to it
+        let height = match opts.max_height {
+            Some(_) => Some(client.latest_height().await?),
+            None => None,
+        };
+
+        if let Some(tx) = client.search_tx(query.clone()).await? {
+            return Ok(tx);
+        }
+        height_deadline.missed(hash, opts, height)?;
+        if Instant::now() > deadline {
+            return Err(ChainError::TxSearchTimeout { tx_hash: *hash });
+        }
+    }
+}
+
+fn check_timeout_height(
+    hash: &Hash,
+    opts: &WaitOptions,
+    height: Option<u64>,
+) -> Result<(), ChainError> {
+    match (opts.max_height, height) {
+        (Some(timeout_height), Some(height)) if height > timeout_height => {
+            Err(ChainError::TxTimeoutHeightExceeded {
+                tx_hash: *hash,
+                timeout_height,
+                height,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Gives up on a tx once the chain is past `max_height` without it. The tx indexer may lag
+/// behind the chain, so the tx has to be missing from a search made at least one block after
+/// the chain went past `max_height` before waiting stops.
+#[derive(Default)]
+struct HeightDeadline {
+    exceeded_at: Option<u64>,
+}
+
+impl HeightDeadline {
+    /// Call after a search for the tx missed, with the chain height read before the search
+    fn missed(
+        &mut self,
+        hash: &Hash,
+        opts: &WaitOptions,
+        height: Option<u64>,
+    ) -> Result<(), ChainError> {
+        if let Err(e) = check_timeout_height(hash, opts, height) {
+            match self.exceeded_at {
+                Some(exceeded_at) if height > Some(exceeded_at) => return Err(e),
+                Some(_) => {}
+                None => self.exceeded_at = height,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn tx_inclusion_from_event(hash: Hash, event: event::Event) -> Result<TxInclusion, ChainError> {
+    match event.data {
+        EventData::Tx { tx_result } => Ok(TxInclusion {
+            hash,
+            height: tx_result.height as u64,
+            gas_wanted: parse_gas(tx_result.result.gas_wanted),
+            gas_used: parse_gas(tx_result.result.gas_used),
+            events: tx_result.result.events,
+        }),
+        _ => Err(ChainError::UnexpectedEvent { query: event.query }),
+    }
+}
+
+pub(crate) fn parse_gas(gas: Option<String>) -> u64 {
+    gas.and_then(|g| g.parse().ok()).unwrap_or_default()
+}
+
+// Subscriptions of one client to the same query share one subscription on the node, and
+// unsubscribing ends all of them. Concurrent waits on one tx are counted per client, so only
+// the last one unsubscribes, and waits through other clients are left alone.
+// Clones of a client share its connection, so they have to wait through the same client.
+fn add_tx_waiter<C>(client: &C, query: &Query) {
+    *TX_WAITERS
+        .lock()
+        .unwrap()
+        .entry(tx_waiter_key(client, query))
+        .or_default() += 1;
+}
+
+/// Whether the caller was the last one waiting on `query` through `client`
+fn remove_tx_waiter<C>(client: &C, query: &Query) -> bool {
+    let mut waiters = TX_WAITERS.lock().unwrap();
+    let key = tx_waiter_key(client, query);
+    match waiters.get_mut(&key) {
+        Some(count) if *count > 1 => {
+            *count -= 1;
+            false
+        }
+        _ => {
+            waiters.remove(&key);
+            true
+        }
+    }
+}
+
+// clients are told apart by their address
+fn tx_waiter_key<C>(client: &C, query: &Query) -> (usize, String) {
+    (client as *const C as usize, query.to_string())
+}
+
+#[async_trait]
+impl WaitForTx for HttpClient {
+    async fn wait_for_tx(
+        &self,
+        hash: &Hash,
+        opts: &WaitOptions,
+    ) -> Result<TxInclusion, ChainError> {
+        Ok(poll_tx_search(self, hash, opts).await?.into())
+    }
+}
+
+#[async_trait]
+impl WaitForTx for WebSocketClient {
+    async fn wait_for_tx(
+        &self,
+        hash: &Hash,
+        opts: &WaitOptions,
+    ) -> Result<TxInclusion, ChainError> {
+        let query = tx_hash_query(hash);
+
+        add_tx_waiter(self, &query);
+        // every early return happens in here, so the subscription is always cleaned up
+        let res = async {
+            let mut subscription = self.subscribe(query.clone()).await?;
+            wait_for_tx_event(self, hash, opts, &query, &mut subscription).await
+        }
+        .await;
+
+        if remove_tx_waiter(self, &query) {
+            if let Err(e) = self.unsubscribe(query).await {
+                debug!("failed to unsubscribe from tx {}: {}", hash, e);
             }
-            if tokio::time::Instant::now() - start_time > Duration::from_secs(30) {
+        }
+
+        res
+    }
+}
+
+async fn wait_for_tx_event(
+    client: &WebSocketClient,
+    hash: &Hash,
+    opts: &WaitOptions,
+    query: &Query,
+    subscription: &mut Subscription,
+) -> Result<TxInclusion, ChainError> {
+    let deadline = Instant::now() + opts.timeout;
+
+    // the tx may have been committed before the subscription was registered
+    if let Some(tx) = client.search_tx(query.clone()).await? {
+        return Ok(tx.into());
+    }
+
+    let mut height_deadline = HeightDeadline::default();
+    let mut interval = tokio::time::interval(opts.poll_interval);
+    loop {
+        tokio::select! {
+            event = subscription.next() => return match event {
+                Some(event) => tx_inclusion_from_event(*hash, event?),
+                // the websocket driver has stopped, so keep looking with plain rpc calls
+                None => {
+                    let opts = WaitOptions {
+                        timeout: deadline.saturating_duration_since(Instant::now()),
+                        ..opts.clone()
+                    };
+                    poll_tx_search(client, hash, &opts).await.map(Into::into)
+                }
+            },
+            _ = tokio::time::sleep_until(deadline) => {
                 return Err(ChainError::TxSearchTimeout { tx_hash: *hash });
             }
+            _ = interval.tick(), if opts.max_height.is_some() => {
+                let height = client.latest_height().await?;
+                if check_timeout_height(hash, opts, Some(height)).is_err() {
+                    // the event may still be in flight, or the tx not indexed yet
+                    if let Some(tx) = client.search_tx(query.clone()).await? {
+                        return Ok(tx.into());
+                    }
+                    height_deadline.missed(hash, opts, Some(height))?;
+                }
+            }
         }
     }
 }
@@ -175,7 +418,6 @@ where
     }
 }
 
-#[cfg_attr(feature = "mockall", automock)]
 #[async_trait]
 pub trait ClientCompat: Client + Sized {
     async fn query_compat_mode(&self) -> Result<CompatMode, ChainError> {
@@ -194,6 +436,7 @@ pub trait ClientCompat: Client + Sized {
 
 lazy_static! {
     static ref COMPAT_MODE: RwLock<Option<CompatMode>> = RwLock::new(None);
+    static ref TX_WAITERS: Mutex<HashMap<(usize, String), usize>> = Mutex::new(HashMap::new());
 }
 
 #[async_trait]
@@ -220,3 +463,129 @@ impl ClientCompat for HttpClient {
         Ok(client)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const HASH: Hash = Hash::Sha256([7; 32]);
+
+    /// Misses `misses` searches before finding the tx, and grows a block per height query
+    struct FakeLookup {
+        misses: Mutex<usize>,
+        height: Mutex<u64>,
+    }
+
+    impl FakeLookup {
+        fn new(misses: usize, height: u64) -> Self {
+            Self {
+                misses: Mutex::new(misses),
+                height: Mutex::new(height),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TxLookup for FakeLookup {
+        async fn search_tx(&self, _query: Query) -> Result<Option<tx::Response>, ChainError> {
+            let mut misses = self.misses.lock().unwrap();
+            if *misses > 0 {
+                *misses -= 1;
+                return Ok(None);
+            }
+            Ok(Some(tx::Response {
+                hash: HASH,
+                height: 7u32.into(),
+                index: 0,
+                tx_result: Default::default(),
+                tx: vec![],
+                proof: None,
+            }))
+        }
+
+        async fn latest_height(&self) -> Result<u64, ChainError> {
+            let mut height = self.height.lock().unwrap();
+            *height += 1;
+            Ok(*height)
+        }
+    }
+
+    fn opts(timeout: Duration, max_height: Option<u64>) -> WaitOptions {
+        WaitOptions {
+            timeout,
+            max_height,
+            poll_interval: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn polling_fallback() {
+        let lookup = FakeLookup::new(3, 0);
+        let tx = poll_tx_search(&lookup, &HASH, &opts(Duration::from_secs(5), None))
+            .await
+            .unwrap();
+        assert_eq!(tx.hash, HASH);
+        assert_eq!(*lookup.misses.lock().unwrap(), 0);
+
+        let lookup = FakeLookup::new(usize::MAX, 10);
+        let err = poll_tx_search(&lookup, &HASH, &opts(Duration::from_secs(5), Some(12)))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ChainError::TxTimeoutHeightExceeded {
+                timeout_height: 12,
+                height: 14,
+                ..
+            }
+        ));
+
+        let err = poll_tx_search(&lookup, &HASH, &opts(Duration::from_millis(20), None))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ChainError::TxSearchTimeout { .. }));
+    }
+
+    #[test]
+    fn timeout_height() {
+        let check = |max_height, height| {
+            check_timeout_height(&HASH, &opts(Duration::ZERO, max_height), height)
+        };
+        assert!(check(None, Some(100)).is_ok());
+        assert!(check(Some(12), None).is_ok());
+        assert!(check(Some(12), Some(12)).is_ok());
+        assert!(matches!(
+            check(Some(12), Some(13)),
+            Err(ChainError::TxTimeoutHeightExceeded {
+                timeout_height: 12,
+                height: 13,
+                ..
+            })
+        ));
+
+        // a miss in the first block past the timeout height may be indexer lag
+        let mut deadline = HeightDeadline::default();
+        let opts = opts(Duration::ZERO, Some(12));
+        assert!(deadline.missed(&HASH, &opts, Some(12)).is_ok());
+        assert!(deadline.missed(&HASH, &opts, Some(13)).is_ok());
+        assert!(deadline.missed(&HASH, &opts, Some(13)).is_ok());
+        assert!(deadline.missed(&HASH, &opts, Some(14)).is_err());
+    }
+
+    #[test]
+    fn last_waiter_unsubscribes() {
+        // stand-ins for two clients, only their addresses matter
+        let (first, second) = (String::from("first"), String::from("second"));
+        let query = tx_hash_query(&HASH);
+
+        add_tx_waiter(&first, &query);
+        add_tx_waiter(&first, &query);
+        add_tx_waiter(&second, &query);
+        assert!(!remove_tx_waiter(&first, &query));
+        // the other client has its own subscription
+        assert!(remove_tx_waiter(&second, &query));
+        assert!(remove_tx_waiter(&first, &query));
+    }
+}
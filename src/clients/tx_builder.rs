@@ -0,0 +1,483 @@
+use cosmrs::tendermint::{abci::Event, Hash, Time};
+use cosmrs::Any;
+use tendermint_rpc::endpoint::broadcast::{tx_async, tx_commit, tx_sync};
+
+use crate::chain::coin::{Coin, Denom};
+use crate::chain::error::ChainError;
+use crate::chain::fee::{Fee, GasInfo};
+use crate::chain::msg::IntoAny;
+use crate::chain::request::TxSignMode;
+use crate::chain::response::Code;
+use crate::chain::tx::RawTx;
+use crate::config::cfg::ChainConfig;
+use crate::modules::auth::error::AccountError;
+use crate::modules::auth::model::{Account, Address};
+use crate::signing_key::signer::{Signer, ToSigner};
+use crate::signing_key::unsigned_tx::{TxSigner, UnsignedTx};
+
+use super::client::{
+    gas_to_fee, ClientAbciQuery, ClientTxAsync, ClientTxCommit, ClientTxSync, GetEvents,
+};
+
+/// Builds one tx out of msgs of any type, e.g. a bank send together with a wasm execute,
+/// which `tx_sign` cannot as it takes msgs of a single type.
+///
+/// The accounts of the signing keys are queried unless given with [`TxBuilder::account`],
+/// and the fee is simulated unless given with [`TxBuilder::fee`].
+#[derive(Clone, Debug, Default)]
+pub struct TxBuilder {
+    msgs: Vec<Any>,
+    memo: String,
+    timeout_height: u64,
+    timeout_timestamp: Option<Time>,
+    fee: Option<Fee>,
+    granter: Option<Address>,
+    sign_mode: TxSignMode,
+    extension_options: Vec<Any>,
+    non_critical_extension_options: Vec<Any>,
+    accounts: Vec<Account>,
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn msg(self, msg: impl IntoAny) -> Result<Self, ChainError> {
+        let msg = msg.into_any().map_err(|e| ChainError::ProtoEncoding {
+            message: e.to_string(),
+        })?;
+        Ok(self.any_msg(msg))
+    }
+
+    /// Adds an already encoded msg, e.g. one of a module this crate has no types for.
+    /// When its signers are not registered with
+    /// [`register_msg_signers`](crate::chain::signers::register_msg_signers),
+    /// the tx can only be signed by a single key, which is taken to be its signer.
+    pub fn any_msg(mut self, msg: Any) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    pub fn msgs(&self) -> &[Any] {
+        &self.msgs
+    }
+
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = memo.into();
+        self
+    }
+
+    pub fn timeout_height(mut self, timeout_height: u64) -> Self {
+        self.timeout_height = timeout_height;
+        self
+    }
+
+    /// Only cosmos-sdk v0.53 and newer chains accept txs with a timeout timestamp
+    pub fn timeout_timestamp(mut self, timeout_timestamp: Time) -> Self {
+        self.timeout_timestamp = Some(timeout_timestamp);
+        self
+    }
+
+    /// Skips the simulation and pays `fee`
+    pub fn fee(mut self, fee: Fee) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    /// Pays the fee from a fee grant of `granter`, overriding the granter of [`TxBuilder::fee`]
+    pub fn granter(mut self, granter: Address) -> Self {
+        self.granter = Some(granter);
+        self
+    }
+
+    pub fn sign_mode(mut self, sign_mode: TxSignMode) -> Self {
+        self.sign_mode = sign_mode;
+        self
+    }
+
+    pub fn extension_option(mut self, option: Any) -> Self {
+        self.extension_options.push(option);
+        self
+    }
+
+    pub fn non_critical_extension_option(mut self, option: Any) -> Self {
+        self.non_critical_extension_options.push(option);
+        self
+    }
+
+    /// Signs with the account number and sequence of `account` instead of querying them
+    pub fn account(mut self, account: Account) -> Self {
+        self.accounts.push(account);
+        self
+    }
+
+    /// Simulates the tx signed by `keys`, with [`TxBuilder::fee`] or a zero fee
+    pub async fn simulate<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&dyn ToSigner],
+    ) -> Result<GasInfo, AccountError>
+    where
+        C: ClientAbciQuery + Sync,
+    {
+        let (_, tx_signers) = self.signers(client, chain_cfg, keys).await?;
+        Ok(self.simulate_with(client, chain_cfg, tx_signers).await?)
+    }
+
+    /// Signs the tx with every key it needs, in any order
+    pub async fn sign<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&dyn ToSigner],
+    ) -> Result<RawTx, AccountError>
+    where
+        C: ClientAbciQuery + Sync,
+    {
+        let (signers, tx_signers) = self.signers(client, chain_cfg, keys).await?;
+
+        let fee = match &self.fee {
+            Some(fee) => fee.clone(),
+            None => {
+                let gas_info = self
+                    .simulate_with(client, chain_cfg, tx_signers.clone())
+                    .await?;
+                gas_to_fee(
+                    &gas_info,
+                    chain_cfg.denom.parse()?,
+                    chain_cfg.gas_price,
+                    chain_cfg.gas_adjustment,
+                )
+            }
+        };
+
+        let tx = self.unsigned_tx(chain_cfg, fee, tx_signers)?;
+        let mut signatures = Vec::with_capacity(signers.len());
+        for signer in &signers {
+            signatures.push(tx.sign(signer.as_ref()).await?);
+        }
+        Ok(tx.combine(signatures)?)
+    }
+
+    pub async fn broadcast_commit<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&dyn ToSigner],
+    ) -> Result<TxResponse, AccountError>
+    where
+        C: ClientTxCommit + ClientAbciQuery + Sync,
+        <C as ClientTxCommit>::Response: Into<TxResponse>,
+    {
+        let raw = self.sign(client, chain_cfg, keys).await?;
+        Ok(client.broadcast_tx_commit(&raw).await?.into())
+    }
+
+    pub async fn broadcast_sync<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&dyn ToSigner],
+    ) -> Result<TxResponse, AccountError>
+    where
+        C: ClientTxSync + ClientAbciQuery + Sync,
+        <C as ClientTxSync>::Response: Into<TxResponse>,
+    {
+        let raw = self.sign(client, chain_cfg, keys).await?;
+        Ok(client.broadcast_tx_sync(&raw).await?.into())
+    }
+
+    pub async fn broadcast_async<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&dyn ToSigner],
+    ) -> Result<TxResponse, AccountError>
+    where
+        C: ClientTxAsync + ClientAbciQuery + Sync,
+        <C as ClientTxAsync>::Response: Into<TxResponse>,
+    {
+        let raw = self.sign(client, chain_cfg, keys).await?;
+        Ok(client.broadcast_tx_async(&raw).await?.into())
+    }
+
+    async fn signers<'a, C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        keys: &[&'a dyn ToSigner],
+    ) -> Result<(Vec<Box<dyn Signer + 'a>>, Vec<TxSigner>), AccountError>
+    where
+        C: ClientAbciQuery + Sync,
+    {
+        let mut signers = Vec::with_capacity(keys.len());
+        let mut tx_signers = Vec::with_capacity(keys.len());
+        for key in keys {
+            let signer = key.to_chain_signer(chain_cfg)?;
+            let address = signer.address(&chain_cfg.prefix).await?;
+            let account = match self.accounts.iter().find(|a| a.address == address) {
+                Some(account) => account.clone(),
+                None => client.auth_query_account(address).await?.account,
+            };
+            tx_signers.push(TxSigner::new(signer.as_ref(), &account).await?);
+            signers.push(signer);
+        }
+        Ok((signers, tx_signers))
+    }
+
+    async fn simulate_with<C>(
+        &self,
+        client: &C,
+        chain_cfg: &ChainConfig,
+        tx_signers: Vec<TxSigner>,
+    ) -> Result<GasInfo, ChainError>
+    where
+        C: ClientAbciQuery + Sync,
+    {
+        let fee = match &self.fee {
+            Some(fee) => fee.clone(),
+            None => {
+                let denom: Denom = chain_cfg.denom.parse()?;
+                Fee::new(Coin { denom, amount: 0 }, 0u64, None, None)
+            }
+        };
+
+        let tx = self.unsigned_tx(chain_cfg, fee, tx_signers)?;
+        client.query_simulate_tx(&tx.simulation_tx()?).await
+    }
+
+    fn unsigned_tx(
+        &self,
+        chain_cfg: &ChainConfig,
+        mut fee: Fee,
+        tx_signers: Vec<TxSigner>,
+    ) -> Result<UnsignedTx, ChainError> {
+        if self.granter.is_some() {
+            fee.granter = self.granter.clone();
+        }
+
+        Ok(UnsignedTx::from_any_msgs(
+            self.msgs.clone(),
+            self.timeout_height,
+            &self.memo,
+            fee,
+            &chain_cfg.chain_id,
+            tx_signers,
+        )?
+        .with_sign_mode(self.sign_mode)
+        .with_timeout_timestamp(self.timeout_timestamp)
+        .with_extension_options(
+            self.extension_options.clone(),
+            self.non_critical_extension_options.clone(),
+        ))
+    }
+}
+
+/// The result of a broadcast in any mode. Sync and async broadcasts return before the tx is
+/// included in a block, so they leave `height`, `gas_wanted` and `gas_used` empty and have no events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxResponse {
+    pub hash: Hash,
+    pub code: Code,
+    pub log: String,
+    pub data: Vec<u8>,
+    pub height: Option<u64>,
+    pub gas_wanted: Option<u64>,
+    pub gas_used: Option<u64>,
+    pub events: Vec<Event>,
+}
+
+impl GetEvents for TxResponse {
+    fn get_events(&self) -> &[Event] {
+        self.events.as_slice()
+    }
+}
+
+impl From<tx_commit::Response> for TxResponse {
+    fn from(res: tx_commit::Response) -> Self {
+        // a tx rejected by `CheckTx` is never delivered
+        if res.check_tx.code.is_err() {
+            return Self {
+                hash: res.hash,
+                code: res.check_tx.code.into(),
+                log: res.check_tx.log,
+                data: res.check_tx.data.into(),
+                height: None,
+                gas_wanted: None,
+                gas_used: None,
+                events: vec![],
+            };
+        }
+
+        Self {
+            hash: res.hash,
+            code: res.deliver_tx.code.into(),
+            log: res.deliver_tx.log,
+            data: res.deliver_tx.data.into(),
+            height: Some(res.height.value()),
+            gas_wanted: Some(res.deliver_tx.gas_wanted as u64),
+            gas_used: Some(res.deliver_tx.gas_used as u64),
+            events: res.deliver_tx.events,
+        }
+    }
+}
+
+impl From<tx_sync::Response> for TxResponse {
+    fn from(res: tx_sync::Response) -> Self {
+        Self {
+            hash: res.hash,
+            code: res.code.into(),
+            log: res.log,
+            data: res.data.into(),
+            height: None,
+            gas_wanted: None,
+            gas_used: None,
+            events: vec![],
+        }
+    }
+}
+
+impl From<tx_async::Response> for TxResponse {
+    fn from(res: tx_async::Response) -> Self {
+        Self {
+            hash: res.hash,
+            code: res.code.into(),
+            log: res.log,
+            data: res.data.into(),
+            height: None,
+            gas_wanted: None,
+            gas_used: None,
+            events: vec![],
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use cosmrs::proto::cosmos::bank::v1beta1::MsgSend;
+    use cosmrs::proto::cosmwasm::wasm::v1::MsgExecuteContract;
+    use cosmrs::proto::traits::{Message, MessageExt, TypeUrl};
+
+    use super::*;
+    use crate::chain::signers::tx_signers;
+    use crate::clients::fake::FakeChain;
+    use crate::clients::recording::{Interaction, RecordingClient};
+
+    #[tokio::test]
+    async fn typed_and_encoded_msgs_in_every_broadcast_mode() {
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, req) = chain.funded_sender().await;
+        let recipient = req.to.clone();
+        // a typed msg next to an already encoded one
+        let builder = TxBuilder::new()
+            .msg(req.clone())
+            .unwrap()
+            .any_msg(req.into_any().unwrap())
+            .memo("builder")
+            .timeout_height(100);
+
+        let gas_info = builder.simulate(&chain, &cfg, &[&key]).await.unwrap();
+        assert_eq!(gas_info.gas_used.value(), 100_000);
+
+        let res = builder
+            .broadcast_commit(&chain, &cfg, &[&key])
+            .await
+            .unwrap();
+        assert!(res.code.is_ok());
+        assert_eq!(res.height, Some(chain.height()));
+        assert_eq!(
+            res.find_event_tags("transfer".to_string(), "amount".to_string())
+                .len(),
+            2
+        );
+
+        let res = builder.broadcast_sync(&chain, &cfg, &[&key]).await.unwrap();
+        assert!(res.code.is_ok());
+        assert_eq!(res.height, None);
+        assert_eq!(chain.balance(&recipient, "utest"), 400);
+        assert_eq!(chain.account(&sender).unwrap().sequence, 2);
+    }
+
+    #[tokio::test]
+    async fn broadcasts_msgs_of_different_modules_in_order() {
+        let cfg = FakeChain::chain_cfg();
+        let chain = RecordingClient::new(FakeChain::new(&cfg.chain_id));
+        let (key, sender, req) = chain.inner().funded_sender().await;
+        let execute = MsgExecuteContract {
+            sender: sender.to_string(),
+            contract: Address::new("juno", &[3; 20]).unwrap().to_string(),
+            msg: br#"{"increment":{}}"#.to_vec(),
+            funds: vec![],
+        };
+        let builder = TxBuilder::new()
+            .msg(req)
+            .unwrap()
+            .any_msg(execute.to_any().unwrap())
+            .fee(Fee::new(FakeChain::coin(50_000), 200_000u64, None, None));
+
+        // the fake chain only runs bank sends, what matters is the tx that was broadcast
+        assert!(builder.broadcast_sync(&chain, &cfg, &[&key]).await.is_err());
+        let tx = match chain.fixture().interactions.pop() {
+            Some(Interaction::BroadcastTxSync { tx, .. }) => tx,
+            interaction => panic!("unexpected interaction {interaction:?}"),
+        };
+        let tx = RawTx::from_bytes(&tx).unwrap().decode().unwrap();
+        let type_urls: Vec<_> = tx.msgs.iter().map(|msg| msg.type_url.as_str()).collect();
+        assert_eq!(
+            type_urls,
+            vec![MsgSend::TYPE_URL, MsgExecuteContract::TYPE_URL]
+        );
+        assert_eq!(
+            MsgExecuteContract::decode(tx.msgs[1].value.as_slice()).unwrap(),
+            execute
+        );
+    }
+
+    #[tokio::test]
+    async fn unregistered_msg_is_signed_by_the_only_key() {
+        let cfg = FakeChain::chain_cfg();
+        let chain = FakeChain::new(&cfg.chain_id);
+        let (key, sender, _) = chain.funded_sender().await;
+        let msg = Any {
+            type_url: "/test.unregistered.v1.MsgDoSomething".to_string(),
+            value: vec![1, 2, 3],
+        };
+        let builder = TxBuilder::new().any_msg(msg).fee(Fee::new(
+            FakeChain::coin(50_000),
+            200_000u64,
+            None,
+            None,
+        ));
+
+        let tx = builder
+            .sign(&chain, &cfg, &[&key])
+            .await
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert!(matches!(
+            tx_signers(&tx.msgs, &tx.fee),
+            Err(ChainError::UnknownMsgSigners { .. })
+        ));
+        assert_eq!(tx.signer_infos.len(), 1);
+        let account = chain
+            .auth_query_account(sender.clone())
+            .await
+            .unwrap()
+            .account;
+        tx.verify_signatures_of(&[sender], &cfg.chain_id, &[account])
+            .unwrap();
+
+        // with several keys there is no telling who signs it
+        let (other, _, _) = chain.funded_sender().await;
+        assert!(matches!(
+            builder.sign(&chain, &cfg, &[&key, &other]).await,
+            Err(AccountError::ChainError(
+                ChainError::UnknownMsgSigners { .. }
+            ))
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::signing_key::algorithm::KeyAlgorithm;
+
 // TODO: Create a way to use the cosmos chain registry instead of manual
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -17,4 +19,36 @@ pub struct ChainConfig {
     pub gas_price: f64,
     /// example: 1.3
     pub gas_adjustment: f64,
+    /// example: eth_secp256k1 for Evmos, together with derivation path m/44'/60'/0'/0/0.
+    /// Defaults to secp256k1, or injective_eth_secp256k1 with the `injective` feature.
+    #[serde(default)]
+    pub key_algorithm: KeyAlgorithm,
+}
+
+impl ChainConfig {
+    /// A config with the default [`KeyAlgorithm`], which keeps compiling when fields are added
+    /// unlike a struct literal
+    pub fn new(
+        denom: impl Into<String>,
+        prefix: impl Into<String>,
+        chain_id: impl Into<String>,
+        derivation_path: impl Into<String>,
+        gas_price: f64,
+        gas_adjustment: f64,
+    ) -> Self {
+        Self {
+            denom: denom.into(),
+            prefix: prefix.into(),
+            chain_id: chain_id.into(),
+            derivation_path: derivation_path.into(),
+            gas_price,
+            gas_adjustment,
+            key_algorithm: KeyAlgorithm::default(),
+        }
+    }
+
+    pub fn with_key_algorithm(mut self, key_algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = key_algorithm;
+        self
+    }
 }
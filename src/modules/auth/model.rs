@@ -5,7 +5,8 @@ use cosmrs::{crypto::PublicKey, AccountId};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::chain::{error::ChainError, request::PaginationResponse};
+use crate::chain::request::PaginationResponse;
+use crate::signing_key::algorithm::public_key_from_any;
 
 use super::error::AccountError;
 
@@ -94,9 +95,8 @@ impl TryFrom<BaseAccount> for Account {
             pubkey: proto
                 .pub_key
                 .map(Into::<cosmrs::Any>::into)
-                .map(PublicKey::try_from)
-                .transpose()
-                .map_err(ChainError::crypto)?,
+                .map(public_key_from_any)
+                .transpose()?,
             account_number: proto.account_number,
             sequence: proto.sequence,
         })
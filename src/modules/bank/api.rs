@@ -17,7 +17,7 @@ use crate::{
     },
     config::cfg::ChainConfig,
     modules::auth::model::Address,
-    signing_key::key::UserKey,
+    signing_key::signer::ToSigner,
 };
 
 use super::{
@@ -269,7 +269,7 @@ pub trait BankTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: SendRequest,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, BankError> {
         self.bank_send_batch_commit(chain_cfg, vec![req], key, tx_options)
@@ -280,7 +280,7 @@ pub trait BankTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, BankError>
     where
@@ -306,7 +306,7 @@ pub trait BankTxSync: ClientTxSync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: SendRequest,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxSync>::Response, BankError> {
         self.bank_send_batch_sync(chain_cfg, vec![req], key, tx_options)
@@ -317,7 +317,7 @@ pub trait BankTxSync: ClientTxSync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxSync>::Response, BankError>
     where
@@ -343,7 +343,7 @@ pub trait BankTxAsync: ClientTxAsync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: SendRequest,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxAsync>::Response, BankError> {
         self.bank_send_batch_async(chain_cfg, vec![req], key, tx_options)
@@ -354,7 +354,7 @@ pub trait BankTxAsync: ClientTxAsync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxAsync>::Response, BankError>
     where
@@ -372,281 +372,153 @@ pub trait BankTxAsync: ClientTxAsync + ClientAbciQuery {
 }
 
 #[cfg(test)]
-#[cfg(feature = "mockall")]
+#[cfg(feature = "mock")]
 mod tests {
-    use crate::{
-        chain::{
-            error::ChainError,
-            response::{ChainResponse, ChainTxResponse, Code},
-        },
-        clients::client::MockCosmosClient,
-        modules::{bank::model::SendResponse, tx::error::TxError},
-    };
-    use cosmrs::proto::{
-        cosmos::auth::v1beta1::{BaseAccount, QueryAccountRequest, QueryAccountResponse},
-        traits::MessageExt,
-    };
+    use cosmrs::tendermint::abci::{response::DeliverTx, Code};
+    use tendermint_rpc::endpoint::abci_query::AbciQuery;
 
     use crate::{
-        chain::{coin::Coin, fee::GasInfo, request::TxOptions},
-        clients::client::CosmTome,
-        config::cfg::ChainConfig,
-        modules::bank::{error::BankError, model::SendRequest},
+        chain::{coin::Coin, error::ChainError, fee::GasInfo, request::TxOptions},
+        clients::{fake::FakeChain, mock::MockClient},
+        modules::{
+            auth::{error::AccountError, model::Account},
+            bank::{error::BankError, model::SendRequest},
+        },
         signing_key::key::UserKey,
     };
 
-    #[tokio::test]
-    async fn test_bank_send_empty() {
-        let cfg = ChainConfig {
-            denom: "utest".to_string(),
-            prefix: "test".to_string(),
-            chain_id: "test-1".to_string(),
-            derivation_path: "m/44'/118'/0'/0/0".to_string(),
-            rpc_endpoint: Some("localhost".to_string()),
-            grpc_endpoint: None,
-            gas_price: 0.1,
-            gas_adjustment: 1.5,
-        };
-
-        let tx_options = TxOptions::default();
-        let key = UserKey::random_mnemonic("test_key".to_string(), cfg.derivation_path.clone());
-
-        let mut mock_client = MockCosmosClient::new();
+    use super::BankTxCommit;
 
-        mock_client
-            .expect_query::<QueryAccountRequest, QueryAccountResponse>()
-            .times(2)
-            .returning(move |_, t: &str| {
-                Ok(QueryAccountResponse {
-                    account: Some(cosmrs::proto::Any {
-                        type_url: t.to_owned(),
-                        value: BaseAccount {
-                            address: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg".to_string(),
-                            pub_key: None,
-                            account_number: 1337,
-                            sequence: 1,
-                        }
-                        .to_bytes()
-                        .unwrap(),
-                    }),
-                })
-            });
-
-        let cosm_tome = CosmTome {
-            cfg: cfg.clone(),
-            client: mock_client,
-        };
+    fn account() -> Account {
+        Account {
+            address: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
+                .parse()
+                .unwrap(),
+            pubkey: None,
+            account_number: 1337,
+            sequence: 1,
+        }
+    }
 
-        // empty amount vec errors:
-        let req = SendRequest {
+    fn send_request(amounts: Vec<Coin>) -> SendRequest {
+        SendRequest {
             from: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
                 .parse()
                 .unwrap(),
             to: "juno1v9xynggs6vnrv2x5ufxdj398u2ghc5n9ya57ea"
                 .parse()
                 .unwrap(),
-            amounts: vec![],
-        };
+            amounts,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bank_send_empty() {
+        let cfg = FakeChain::chain_cfg();
+        let tx_options = TxOptions::default();
+        let key = UserKey::random_mnemonic("test_key".to_string());
+
+        let mut mock_client = MockClient::new();
+        mock_client.expect_account(account());
 
-        let res = cosm_tome
-            .bank_send(req, &key, &tx_options)
+        // empty amount vec errors:
+        let res = mock_client
+            .bank_send_commit(&cfg, send_request(vec![]), &key, &tx_options)
             .await
             .err()
             .unwrap();
 
         assert!(matches!(
             res,
-            BankError::TxError(TxError::ChainError(ChainError::ProtoEncoding { .. }))
+            BankError::AccountError(AccountError::ChainError(ChainError::ProtoEncoding { .. }))
         ));
 
         // coin with 0 value errors:
-        let req = SendRequest {
-            from: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
-                .parse()
-                .unwrap(),
-            to: "juno1v9xynggs6vnrv2x5ufxdj398u2ghc5n9ya57ea"
-                .parse()
-                .unwrap(),
-            amounts: vec![
-                Coin {
-                    denom: cfg.denom.parse().unwrap(),
-                    amount: 10,
-                },
-                Coin {
-                    denom: cfg.denom.parse().unwrap(),
-                    amount: 0,
-                },
-            ],
-        };
-
-        let res = cosm_tome
-            .bank_send(req, &key, &tx_options)
+        let amounts = vec![
+            Coin {
+                denom: cfg.denom.parse().unwrap(),
+                amount: 10,
+            },
+            Coin {
+                denom: cfg.denom.parse().unwrap(),
+                amount: 0,
+            },
+        ];
+        let res = mock_client
+            .bank_send_commit(&cfg, send_request(amounts), &key, &tx_options)
             .await
             .err()
             .unwrap();
 
         assert!(matches!(
             res,
-            BankError::TxError(TxError::ChainError(ChainError::ProtoEncoding { .. }))
+            BankError::AccountError(AccountError::ChainError(ChainError::ProtoEncoding { .. }))
         ));
     }
 
     #[tokio::test]
     async fn test_bank_send() {
-        let cfg = ChainConfig {
-            denom: "utest".to_string(),
-            prefix: "test".to_string(),
-            chain_id: "test-1".to_string(),
-            derivation_path: "m/44'/118'/0'/0/0".to_string(),
-            rpc_endpoint: None,
-            grpc_endpoint: None,
-            gas_price: 0.1,
-            gas_adjustment: 1.5,
-        };
+        let cfg = FakeChain::chain_cfg();
         let tx_options = TxOptions::default();
-        let key = UserKey::random_mnemonic("test_key".to_string(), cfg.derivation_path.clone());
-
-        let mut mock_client = MockCosmosClient::new();
+        let key = UserKey::random_mnemonic("test_key".to_string());
 
+        let mut mock_client = MockClient::new();
         mock_client
-            .expect_query::<QueryAccountRequest, QueryAccountResponse>()
-            .times(1)
-            .returning(move |_, t: &str| {
-                Ok(QueryAccountResponse {
-                    account: Some(cosmrs::proto::Any {
-                        type_url: t.to_owned(),
-                        value: BaseAccount {
-                            address: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg".to_string(),
-                            pub_key: None,
-                            account_number: 1337,
-                            sequence: 1,
-                        }
-                        .to_bytes()
-                        .unwrap(),
-                    }),
-                })
+            .expect_account(account())
+            .expect_simulate(GasInfo::new(200u64, 100u64))
+            .expect_commit(DeliverTx {
+                log: "log log log".to_string(),
+                gas_wanted: 200,
+                gas_used: 100,
+                ..Default::default()
             });
 
-        mock_client.expect_simulate_tx().times(1).returning(|_| {
-            Ok(GasInfo {
-                gas_wanted: 200u16.into(),
-                gas_used: 100u16.into(),
-            })
-        });
-
-        mock_client
-            .expect_broadcast_tx_block()
-            .times(1)
-            .returning(|_| {
-                Ok(ChainTxResponse {
-                    res: ChainResponse {
-                        code: Code::Ok,
-                        data: None,
-                        log: "log log log".to_string(),
-                    },
-                    events: vec![],
-                    gas_wanted: 200,
-                    gas_used: 100,
-                    tx_hash: "TX_HASH_0".to_string(),
-                    height: 1337,
-                })
-            });
-
-        let cosm_tome = CosmTome {
-            cfg: cfg.clone(),
-            client: mock_client,
-        };
-
-        let req = SendRequest {
-            from: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
-                .parse()
-                .unwrap(),
-            to: "juno1v9xynggs6vnrv2x5ufxdj398u2ghc5n9ya57ea"
-                .parse()
-                .unwrap(),
-            amounts: vec![Coin {
-                denom: cfg.denom.parse().unwrap(),
-                amount: 10,
-            }],
-        };
-
-        let res = cosm_tome.bank_send(req, &key, &tx_options).await.unwrap();
+        let amounts = vec![Coin {
+            denom: cfg.denom.parse().unwrap(),
+            amount: 10,
+        }];
+        let res = mock_client
+            .bank_send_commit(&cfg, send_request(amounts), &key, &tx_options)
+            .await
+            .unwrap();
 
-        assert_eq!(
-            res,
-            SendResponse {
-                res: ChainTxResponse {
-                    res: ChainResponse {
-                        code: Code::Ok,
-                        data: None,
-                        log: "log log log".to_string()
-                    },
-                    events: vec![],
-                    gas_wanted: 200,
-                    gas_used: 100,
-                    tx_hash: "TX_HASH_0".to_string(),
-                    height: 1337
-                }
-            }
-        );
+        assert_eq!(res.deliver_tx.log, "log log log");
+        assert_eq!(res.deliver_tx.gas_wanted, 200);
+        assert_eq!(res.deliver_tx.gas_used, 100);
     }
 
     #[tokio::test]
     async fn test_bank_send_account_err() {
-        let cfg = ChainConfig {
-            denom: "utest".to_string(),
-            prefix: "test".to_string(),
-            chain_id: "test-1".to_string(),
-            derivation_path: "m/44'/118'/0'/0/0".to_string(),
-            rpc_endpoint: None,
-            grpc_endpoint: None,
-            gas_price: 0.1,
-            gas_adjustment: 1.5,
-        };
-
+        let cfg = FakeChain::chain_cfg();
         let tx_options = TxOptions::default();
-        let key = UserKey::random_mnemonic("test_key".to_string(), cfg.derivation_path.clone());
-
-        let mut mock_client = MockCosmosClient::new();
+        let key = UserKey::random_mnemonic("test_key".to_string());
 
+        let mut mock_client = MockClient::new();
         mock_client
-            .expect_query::<QueryAccountRequest, QueryAccountResponse>()
+            .expect_abci_query()
             .times(1)
-            .returning(move |_, _| {
-                Err(ChainError::CosmosSdk {
-                    res: ChainResponse {
-                        code: Code::Err(1),
-                        data: None,
-                        log: "error".to_string(),
-                    },
+            .returning(|_, _, _, _| {
+                Ok(AbciQuery {
+                    code: Code::from(1u32),
+                    log: "error".to_string(),
+                    ..Default::default()
                 })
             });
 
-        let cosm_tome = CosmTome {
-            cfg: cfg.clone(),
-            client: mock_client,
-        };
-
-        let req = SendRequest {
-            from: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
-                .parse()
-                .unwrap(),
-            to: "juno1v9xynggs6vnrv2x5ufxdj398u2ghc5n9ya57ea"
-                .parse()
-                .unwrap(),
-            amounts: vec![Coin {
-                denom: cfg.denom.parse().unwrap(),
-                amount: 10,
-            }],
-        };
-
-        let res = cosm_tome
-            .bank_send(req, &key, &tx_options)
+        let amounts = vec![Coin {
+            denom: cfg.denom.parse().unwrap(),
+            amount: 10,
+        }];
+        let res = mock_client
+            .bank_send_commit(&cfg, send_request(amounts), &key, &tx_options)
             .await
             .err()
             .unwrap();
 
-        assert!(matches!(res, BankError::TxError(TxError::AccountError(..))));
+        assert!(matches!(
+            res,
+            BankError::AccountError(AccountError::ChainError(ChainError::AbciQuery { .. }))
+        ));
     }
 
     // TODO: Add more happy path tests for other functions
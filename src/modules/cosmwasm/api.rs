@@ -11,7 +11,7 @@ use cosmrs::proto::cosmwasm::wasm::v1::{
 };
 
 use crate::modules::auth::model::Address;
-use crate::signing_key::key::UserKey;
+use crate::signing_key::signer::ToSigner;
 
 use super::model::{
     ExecRequest, InstantiateBatchResponse, InstantiateRequest, MigrateRequest,
@@ -30,7 +30,7 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: StoreCodeRequest,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<StoreCodeResponse<<Self as ClientTxCommit>::Response>, CosmwasmError> {
         let mut res = self
@@ -47,15 +47,13 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<StoreCodeBatchResponse<<Self as ClientTxCommit>::Response>, CosmwasmError>
     where
         I: IntoIterator<Item = StoreCodeRequest> + Send,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let sender_addr = key.to_chain_addr(chain_cfg).await?;
 
         let msgs = reqs
             .into_iter()
@@ -94,7 +92,7 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: InstantiateRequest<S>,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<InstantiateResponse<<Self as ClientTxCommit>::Response>, CosmwasmError>
     where
@@ -114,16 +112,14 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<InstantiateBatchResponse<<Self as ClientTxCommit>::Response>, CosmwasmError>
     where
         S: Serialize + Send,
         I: IntoIterator<Item = InstantiateRequest<S>> + Send,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let sender_addr = key.to_chain_addr(chain_cfg).await?;
 
         let msgs = reqs
             .into_iter()
@@ -163,7 +159,7 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: ExecRequest<S>,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, CosmwasmError>
     where
@@ -177,16 +173,14 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, CosmwasmError>
     where
         S: Serialize + Send,
         I: IntoIterator<Item = ExecRequest<S>> + Send,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let sender_addr = key.to_chain_addr(chain_cfg).await?;
 
         let msgs = reqs
             .into_iter()
@@ -204,7 +198,7 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: MigrateRequest<S>,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, CosmwasmError>
     where
@@ -218,16 +212,14 @@ pub trait CosmwasmTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, CosmwasmError>
     where
         S: Serialize + Send,
         I: IntoIterator<Item = MigrateRequest<S>> + Send,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let sender_addr = key.to_chain_addr(chain_cfg).await?;
 
         let msgs = reqs
             .into_iter()
@@ -250,7 +242,7 @@ pub trait CosmwasmTxAsync: ClientTxAsync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: ExecRequest<S>,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxAsync>::Response, CosmwasmError>
     where
@@ -264,16 +256,14 @@ pub trait CosmwasmTxAsync: ClientTxAsync + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         reqs: I,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxAsync>::Response, CosmwasmError>
     where
         S: Serialize + Send,
         I: IntoIterator<Item = ExecRequest<S>> + Send,
     {
-        let sender_addr = key
-            .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
-            .await?;
+        let sender_addr = key.to_chain_addr(chain_cfg).await?;
 
         let msgs = reqs
             .into_iter()
@@ -344,3 +334,139 @@ pub trait CosmwasmQuery: ClientAbciQuery {
         Ok(res)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "mock")]
+mod tests {
+    use cosmrs::proto::cosmwasm::wasm::v1::QuerySmartContractStateResponse;
+    use cosmrs::proto::traits::MessageExt;
+    use cosmrs::tendermint::abci::{response::DeliverTx, Event, EventAttribute};
+    use serde::Serialize;
+    use tendermint_rpc::endpoint::abci_query::AbciQuery;
+
+    use crate::{
+        chain::{fee::GasInfo, request::TxOptions},
+        clients::{fake::FakeChain, mock::MockClient},
+        modules::{
+            auth::model::{Account, Address},
+            cosmwasm::model::{ExecRequest, InstantiateRequest},
+        },
+        signing_key::key::UserKey,
+    };
+
+    use super::{CosmwasmQuery, CosmwasmTxCommit};
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum ExecuteMsg {
+        Increment {},
+    }
+
+    fn account() -> Account {
+        Account {
+            address: "juno10j9gpw9t4jsz47qgnkvl5n3zlm2fz72k67rxsg"
+                .parse()
+                .unwrap(),
+            pubkey: None,
+            account_number: 1337,
+            sequence: 1,
+        }
+    }
+
+    fn contract() -> Address {
+        Address::new("juno", &[1; 32]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_wasm_query() {
+        let mut mock_client = MockClient::new();
+        mock_client
+            .expect_abci_query()
+            .withf(|path, _, _, _| {
+                path.as_deref() == Some("/cosmwasm.wasm.v1.Query/SmartContractState")
+            })
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(AbciQuery {
+                    value: QuerySmartContractStateResponse {
+                        data: br#"{"count":1}"#.to_vec(),
+                    }
+                    .to_bytes()
+                    .unwrap(),
+                    ..Default::default()
+                })
+            });
+
+        let res = mock_client
+            .wasm_query(contract(), &serde_json::json!({ "get_count": {} }), None)
+            .await
+            .unwrap();
+
+        assert_eq!(res.value.data, br#"{"count":1}"#.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_wasm_execute() {
+        let cfg = FakeChain::chain_cfg();
+        let key = UserKey::random_mnemonic("test_key".to_string());
+
+        let mut mock_client = MockClient::new();
+        mock_client
+            .expect_account(account())
+            .expect_simulate(GasInfo::new(200u64, 100u64))
+            .expect_commit(DeliverTx {
+                gas_wanted: 200,
+                gas_used: 100,
+                ..Default::default()
+            });
+
+        let req = ExecRequest {
+            address: contract(),
+            msg: ExecuteMsg::Increment {},
+            funds: vec![],
+        };
+        let res = mock_client
+            .wasm_execute_commit(&cfg, req, &key, &TxOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(res.deliver_tx.gas_used, 100);
+    }
+
+    #[cfg(not(feature = "injective"))]
+    #[tokio::test]
+    async fn test_wasm_instantiate() {
+        let cfg = FakeChain::chain_cfg();
+        let key = UserKey::random_mnemonic("test_key".to_string());
+
+        let mut mock_client = MockClient::new();
+        mock_client
+            .expect_account(account())
+            .expect_simulate(GasInfo::new(200u64, 100u64))
+            .expect_commit(DeliverTx {
+                events: vec![Event {
+                    kind: "instantiate".to_string(),
+                    attributes: vec![EventAttribute {
+                        key: "_contract_address".to_string(),
+                        value: contract().to_string(),
+                        index: true,
+                    }],
+                }],
+                ..Default::default()
+            });
+
+        let req = InstantiateRequest {
+            code_id: 1,
+            msg: serde_json::json!({ "count": 0 }),
+            label: "counter".to_string(),
+            admin: None,
+            funds: vec![],
+        };
+        let res = mock_client
+            .wasm_instantiate_commit(&cfg, req, &key, &TxOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(res.address, contract());
+    }
+}
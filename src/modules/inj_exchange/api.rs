@@ -12,7 +12,7 @@ use crate::{
     chain::request::TxOptions,
     clients::client::{ClientAbciQuery, ClientTxCommit, QueryResponse},
     config::cfg::ChainConfig,
-    signing_key::key::UserKey,
+    signing_key::signer::ToSigner,
 };
 
 use super::error::ExchangeError;
@@ -122,7 +122,7 @@ pub trait ExchangeTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: MsgBatchUpdateOrders,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, ExchangeError> {
         let tx_raw = self.tx_sign(chain_cfg, vec![req], key, tx_options).await?;
@@ -134,7 +134,7 @@ pub trait ExchangeTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: MsgCreateSpotLimitOrder,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, ExchangeError> {
         let tx_raw = self.tx_sign(chain_cfg, vec![req], key, tx_options).await?;
@@ -146,7 +146,7 @@ pub trait ExchangeTxCommit: ClientTxCommit + ClientAbciQuery {
         &self,
         chain_cfg: &ChainConfig,
         req: MsgBatchCreateSpotLimitOrders,
-        key: &UserKey,
+        key: &dyn ToSigner,
         tx_options: &TxOptions,
     ) -> Result<<Self as ClientTxCommit>::Response, ExchangeError> {
         let tx_raw = self.tx_sign(chain_cfg, vec![req], key, tx_options).await?;
@@ -0,0 +1,198 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cosmrs::crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::chain::amino::{AminoMsg, StdFee, StdSignDoc};
+use crate::chain::error::ChainError;
+use crate::modules::auth::model::Address;
+
+use super::algorithm::{secp256k1_public_key, KeyAlgorithm};
+use super::signer::Signer;
+
+/// The ADR-036 sign doc of `data`: an amino JSON `sign/MsgSignData` with an empty chain id,
+/// no fee and zero account number and sequence, as Keplr's `signArbitrary` builds it
+pub fn arbitrary_sign_doc(signer: &Address, data: &[u8]) -> StdSignDoc {
+    StdSignDoc {
+        account_number: "0".to_string(),
+        chain_id: String::new(),
+        fee: StdFee {
+            amount: vec![],
+            gas: "0".to_string(),
+            payer: String::new(),
+            granter: String::new(),
+        },
+        memo: String::new(),
+        msgs: vec![AminoMsg {
+            kind: "sign/MsgSignData".to_string(),
+            value: json!({
+                "data": STANDARD.encode(data),
+                "signer": signer.to_string(),
+            }),
+        }],
+        sequence: "0".to_string(),
+        timeout_height: None,
+    }
+}
+
+/// Signs `data` off-chain with ADR-036, returning what Keplr's `signArbitrary` returns
+pub async fn sign_arbitrary(
+    signer: &dyn Signer,
+    prefix: &str,
+    data: &[u8],
+) -> Result<StdSignature, ChainError> {
+    let address = signer.address(prefix).await?;
+    let sign_bytes = arbitrary_sign_doc(&address, data).to_sign_bytes()?;
+
+    Ok(StdSignature {
+        pub_key: AminoPubKey::new(&signer.public_key().await?, signer.algorithm()),
+        signature: STANDARD.encode(signer.sign(&sign_bytes).await?),
+    })
+}
+
+/// Checks that `signature` is an ADR-036 signature of `data` by `address`,
+/// and that `public_key` belongs to `address`
+pub fn verify_arbitrary(
+    address: &Address,
+    public_key: &PublicKey,
+    data: &[u8],
+    signature: &[u8],
+) -> bool {
+    let Ok(sign_bytes) = arbitrary_sign_doc(address, data).to_sign_bytes() else {
+        return false;
+    };
+
+    // cosmos and ethermint keys have different addresses, which tells which digest was signed
+    KeyAlgorithm::from_address(public_key, address)
+        .is_some_and(|algorithm| algorithm.verify(public_key, &sign_bytes, signature))
+}
+
+/// A signature with its public key in amino JSON, `StdSignature` in the cosmos-sdk
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StdSignature {
+    pub pub_key: AminoPubKey,
+    /// base64 encoded
+    pub signature: String,
+}
+
+impl StdSignature {
+    pub fn public_key(&self) -> Result<PublicKey, ChainError> {
+        let bytes = STANDARD
+            .decode(&self.pub_key.value)
+            .map_err(|e| ChainError::Crypto {
+                message: format!("invalid public key: {e}"),
+            })?;
+        secp256k1_public_key(&bytes).ok_or_else(|| ChainError::Crypto {
+            message: format!("invalid secp256k1 public key {}", hex::encode(bytes)),
+        })
+    }
+
+    /// Checks the signature as [`verify_arbitrary`] does
+    pub fn verify(&self, address: &Address, data: &[u8]) -> Result<bool, ChainError> {
+        let signature = STANDARD
+            .decode(&self.signature)
+            .map_err(|e| ChainError::Crypto {
+                message: format!("invalid signature: {e}"),
+            })?;
+        Ok(verify_arbitrary(
+            address,
+            &self.public_key()?,
+            data,
+            &signature,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AminoPubKey {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// base64 encoded compressed key
+    pub value: String,
+}
+
+impl AminoPubKey {
+    pub fn new(public_key: &PublicKey, algorithm: KeyAlgorithm) -> Self {
+        let kind = match algorithm {
+            KeyAlgorithm::Secp256k1 => "tendermint/PubKeySecp256k1",
+            KeyAlgorithm::EthSecp256k1 => "ethermint/PubKeyEthSecp256k1",
+            KeyAlgorithm::InjectiveEthSecp256k1 => "injective/PubKeyEthSecp256k1",
+        };
+
+        Self {
+            kind: kind.to_string(),
+            value: STANDARD.encode(public_key.to_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing_key::signer::LocalSigner;
+
+    const SIGNER: &str = "cosmos1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu";
+
+    // the sign doc Keplr's `makeADR36AminoSignDoc` builds for the same signer and data
+    #[test]
+    fn sign_doc_fixture() {
+        let doc = arbitrary_sign_doc(&SIGNER.parse().unwrap(), b"Login challenge: 8c1f<>&");
+        assert_eq!(
+            String::from_utf8(doc.to_sign_bytes().unwrap()).unwrap(),
+            format!(
+                r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"TG9naW4gY2hhbGxlbmdlOiA4YzFmPD4m","signer":"{SIGNER}"}}}}],"sequence":"0"}}"#
+            )
+        );
+    }
+
+    // secp256k1 signatures are deterministic (RFC 6979), so the same mnemonic and data
+    // give the signature Keplr's `signArbitrary` returns byte for byte
+    #[tokio::test]
+    async fn sign_arbitrary_fixture() {
+        let signer = LocalSigner::from_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "m/44'/118'/0'/0/0",
+        )
+        .unwrap();
+        let data = b"Login challenge: 8c1f<>&";
+
+        let signature = sign_arbitrary(&signer, "cosmos", data).await.unwrap();
+        assert_eq!(
+            signature,
+            StdSignature {
+                pub_key: AminoPubKey {
+                    kind: "tendermint/PubKeySecp256k1".to_string(),
+                    value: "Ak9OKtmcNNYLm6YoPJQxqEGK+GcyEpYfl6d7Y3f80Fti".to_string(),
+                },
+                signature: "AMxGvZcSyq93KNOLE5+TH4EdvP50gWU7bu+dcosWJ5UMMvIlEq38j2lFiKtybcW2oYxCmwlrVZ2k0uG6SluQqA==".to_string(),
+            }
+        );
+        assert!(signature
+            .verify(
+                &"cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4"
+                    .parse()
+                    .unwrap(),
+                data
+            )
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn sign_and_verify() {
+        for algorithm in [KeyAlgorithm::Secp256k1, KeyAlgorithm::EthSecp256k1] {
+            let signer = LocalSigner::from_raw(&[7; 32])
+                .unwrap()
+                .with_algorithm(algorithm);
+            let address = signer.address("cosmos").await.unwrap();
+
+            let signature = sign_arbitrary(&signer, "cosmos", b"challenge")
+                .await
+                .unwrap();
+            assert!(signature.verify(&address, b"challenge").unwrap());
+            assert!(!signature.verify(&address, b"other challenge").unwrap());
+
+            let other: Address = SIGNER.parse().unwrap();
+            assert!(!signature.verify(&other, b"challenge").unwrap());
+        }
+    }
+}
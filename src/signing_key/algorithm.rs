@@ -0,0 +1,212 @@
+use cosmrs::bip32::secp256k1::ecdsa::{
+    signature::hazmat::PrehashVerifier, Signature, VerifyingKey,
+};
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::crypto::secp256k1::PubKey as ProtoPubKey;
+use cosmrs::proto::traits::Message;
+use cosmrs::tx::SignerPublicKey;
+use cosmrs::{AccountId, Any};
+use ethers::utils::keccak256;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::chain::error::ChainError;
+use crate::modules::auth::model::Address;
+
+pub const ETH_SECP256K1_TYPE_URL: &str = "/ethermint.crypto.v1.ethsecp256k1.PubKey";
+pub const INJECTIVE_ETH_SECP256K1_TYPE_URL: &str = "/injective.crypto.v1beta1.ethsecp256k1.PubKey";
+
+/// How a secp256k1 key signs, and how its public key and address are derived.
+/// Set per chain with `ChainConfig::key_algorithm`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    /// Cosmos SDK keys: sha256 sign digests and ripemd160(sha256(pubkey)) addresses
+    Secp256k1,
+    /// Ethermint keys used by Evmos, Cronos, Kava and other EVM chains:
+    /// keccak256 sign digests and ethereum addresses, usually with coin type 60
+    EthSecp256k1,
+    /// Ethermint keys under Injective's own public key type url
+    InjectiveEthSecp256k1,
+}
+
+impl Default for KeyAlgorithm {
+    #[cfg(not(feature = "injective"))]
+    fn default() -> Self {
+        KeyAlgorithm::Secp256k1
+    }
+
+    #[cfg(feature = "injective")]
+    fn default() -> Self {
+        KeyAlgorithm::InjectiveEthSecp256k1
+    }
+}
+
+impl KeyAlgorithm {
+    /// Algorithm of an account public key with type url `type_url`
+    pub fn from_type_url(type_url: &str) -> Option<Self> {
+        match type_url {
+            "/cosmos.crypto.secp256k1.PubKey" => Some(KeyAlgorithm::Secp256k1),
+            ETH_SECP256K1_TYPE_URL => Some(KeyAlgorithm::EthSecp256k1),
+            INJECTIVE_ETH_SECP256K1_TYPE_URL => Some(KeyAlgorithm::InjectiveEthSecp256k1),
+            _ => None,
+        }
+    }
+
+    /// Algorithm whose address of `public_key` is `address`. Ethermint keys resolve to
+    /// [`KeyAlgorithm::EthSecp256k1`], which signs and derives addresses like Injective's.
+    pub fn from_address(public_key: &PublicKey, address: &Address) -> Option<Self> {
+        [KeyAlgorithm::Secp256k1, KeyAlgorithm::EthSecp256k1]
+            .into_iter()
+            .find(|algorithm| {
+                matches!(algorithm.address(public_key, address.prefix()), Ok(a) if &a == address)
+            })
+    }
+
+    fn is_eth(&self) -> bool {
+        matches!(
+            self,
+            KeyAlgorithm::EthSecp256k1 | KeyAlgorithm::InjectiveEthSecp256k1
+        )
+    }
+
+    pub fn address(&self, public_key: &PublicKey, prefix: &str) -> Result<Address, ChainError> {
+        let account = match self {
+            KeyAlgorithm::Secp256k1 => public_key.account_id(prefix),
+            KeyAlgorithm::EthSecp256k1 | KeyAlgorithm::InjectiveEthSecp256k1 => {
+                let uncompressed = VerifyingKey::from_sec1_bytes(&public_key.to_bytes())
+                    .map_err(|e| ChainError::Crypto {
+                        message: e.to_string(),
+                    })?
+                    .to_encoded_point(false);
+                // the address is the last 20 bytes of the hash, skipping the 0x04 point tag
+                let hash = keccak256(&uncompressed.as_bytes()[1..]);
+                AccountId::new(prefix, &hash[12..])
+            }
+        };
+        Ok(account.map_err(ChainError::crypto)?.into())
+    }
+
+    /// Public key as it is put in the `SignerInfo` of a tx
+    pub fn signer_public_key(&self, public_key: PublicKey) -> SignerPublicKey {
+        let type_url = match self {
+            KeyAlgorithm::Secp256k1 => return SignerPublicKey::Single(public_key),
+            KeyAlgorithm::EthSecp256k1 => ETH_SECP256K1_TYPE_URL,
+            KeyAlgorithm::InjectiveEthSecp256k1 => INJECTIVE_ETH_SECP256K1_TYPE_URL,
+        };
+        // ethermint pubkeys have the same proto layout as cosmos secp256k1 ones
+        SignerPublicKey::Any(Any {
+            type_url: type_url.to_string(),
+            value: ProtoPubKey {
+                key: public_key.to_bytes(),
+            }
+            .encode_to_vec(),
+        })
+    }
+
+    /// Checks an ECDSA `signature` of `public_key` over the sign bytes `msg`
+    pub fn verify(&self, public_key: &PublicKey, msg: &[u8], signature: &[u8]) -> bool {
+        let (Ok(key), Ok(signature)) = (
+            VerifyingKey::from_sec1_bytes(&public_key.to_bytes()),
+            Signature::try_from(signature),
+        ) else {
+            return false;
+        };
+        key.verify_prehash(&self.digest(msg), &signature).is_ok()
+    }
+
+    /// Hash of the sign bytes that is signed with ECDSA
+    pub fn digest(&self, bytes: &[u8]) -> [u8; 32] {
+        if self.is_eth() {
+            keccak256(bytes)
+        } else {
+            Sha256::digest(bytes).into()
+        }
+    }
+}
+
+/// Parses a compressed or uncompressed secp256k1 public key
+pub(crate) fn secp256k1_public_key(bytes: &[u8]) -> Option<PublicKey> {
+    cosmrs::tendermint::PublicKey::from_raw_secp256k1(bytes).map(Into::into)
+}
+
+/// Parses an account public key, accepting both cosmos and eth secp256k1 keys
+pub(crate) fn public_key_from_any(any: Any) -> Result<PublicKey, ChainError> {
+    if !matches!(KeyAlgorithm::from_type_url(&any.type_url), Some(algorithm) if algorithm.is_eth())
+    {
+        return PublicKey::try_from(any).map_err(ChainError::crypto);
+    }
+
+    let proto =
+        ProtoPubKey::decode(any.value.as_slice()).map_err(ChainError::prost_proto_decoding)?;
+    secp256k1_public_key(&proto.key).ok_or_else(|| ChainError::Crypto {
+        message: format!(
+            "invalid eth_secp256k1 public key {}",
+            hex::encode(&proto.key)
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eth_public_keys_round_trip_through_any() {
+        // the secp256k1 generator point
+        let bytes =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let public_key = secp256k1_public_key(&bytes).unwrap();
+
+        for algorithm in [
+            KeyAlgorithm::EthSecp256k1,
+            KeyAlgorithm::InjectiveEthSecp256k1,
+        ] {
+            let any = match algorithm.signer_public_key(public_key) {
+                SignerPublicKey::Any(any) => any,
+                _ => panic!("eth keys are encoded as Any"),
+            };
+            assert_eq!(KeyAlgorithm::from_type_url(&any.type_url), Some(algorithm));
+            assert_eq!(public_key_from_any(any).unwrap(), public_key);
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn key_algorithm_is_selected_per_chain() {
+        use crate::chain::request::TxOptions;
+        use crate::clients::fake::FakeChain;
+        use crate::config::cfg::ChainConfig;
+        use crate::modules::bank::{api::BankTxCommit, model::SendRequest};
+        use crate::signing_key::{key::UserKey, signer::ToSigner};
+
+        let key = UserKey::random_mnemonic("sender".to_string());
+        let cosmos_cfg = FakeChain::chain_cfg().with_key_algorithm(KeyAlgorithm::Secp256k1);
+        let evmos_cfg = ChainConfig {
+            prefix: "evmos".to_string(),
+            chain_id: "evmos_9001-2".to_string(),
+            derivation_path: "m/44'/60'/0'/0/0".to_string(),
+            ..FakeChain::chain_cfg()
+        }
+        .with_key_algorithm(KeyAlgorithm::EthSecp256k1);
+
+        for cfg in [cosmos_cfg, evmos_cfg] {
+            let chain = FakeChain::new(&cfg.chain_id);
+            let sender = key.to_chain_addr(&cfg).await.unwrap();
+            chain.fund(&sender, FakeChain::coin(1_000_000));
+
+            let req = SendRequest {
+                from: sender.clone(),
+                to: Address::new(&cfg.prefix, &[2; 20]).unwrap(),
+                amounts: vec![FakeChain::coin(100)],
+            };
+            chain
+                .bank_send_commit(&cfg, req, &key, &TxOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(chain.account(&sender).unwrap().sequence, 1);
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
 use cosmrs::bip32;
 use cosmrs::bip32::secp256k1::elliptic_curve::rand_core::OsRng;
-use cosmrs::crypto::{secp256k1, PublicKey};
-use cosmrs::tendermint::block::Height;
-use cosmrs::tx::{Body, SignDoc, SignerInfo};
-
+use cosmrs::bip32::secp256k1::elliptic_curve::zeroize::Zeroizing;
+use cosmrs::crypto::PublicKey;
 #[cfg(feature = "keyring")]
 use keyring::Entry;
 use schemars::JsonSchema;
@@ -13,38 +15,43 @@ use crate::chain::error::ChainError;
 use crate::chain::fee::Fee;
 use crate::chain::msg::IntoAny;
 use crate::chain::tx::RawTx;
+use crate::config::cfg::ChainConfig;
 use crate::modules::auth::model::{Account, Address};
 
+use super::adr036::{sign_arbitrary, StdSignature};
+use super::algorithm::KeyAlgorithm;
+use super::keystore::{read_keystore, write_keystore, PassphraseSource};
+#[cfg(feature = "keyring")]
+use super::signer::KeyringSigner;
+use super::signer::{
+    mnemonic_to_seed, seed_to_public_key, seed_to_secret, sign_tx, CachedSigner, LocalSigner,
+    Signer, ToSigner,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct UserKey {
     /// human readable key name
     pub name: String,
     /// private key associated with `name`
     pub key: Key,
+    #[serde(skip)]
+    #[schemars(skip)]
+    seed: SeedCache,
 }
 
 impl UserKey {
-    pub async fn public_key(&self, derivation_path: &str) -> Result<PublicKey, ChainError> {
-        match &self.key {
-            Key::Raw(bytes) => {
-                let key = raw_bytes_to_signing_key(bytes)?;
-                Ok(key.public_key())
-            }
-
-            Key::Mnemonic(phrase) => {
-                let key = mnemonic_to_signing_key(phrase, derivation_path)?;
-                Ok(key.public_key())
-            }
-
-            #[cfg(feature = "keyring")]
-            Key::Keyring(params) => {
-                let entry = Entry::new(&self.name, &params.user)?;
-                let key = mnemonic_to_signing_key(&entry.get_password()?, derivation_path)?;
-                Ok(key.public_key())
-            }
+    pub fn new(name: impl Into<String>, key: Key) -> Self {
+        Self {
+            name: name.into(),
+            key,
+            seed: SeedCache::default(),
         }
     }
 
+    pub async fn public_key(&self, derivation_path: &str) -> Result<PublicKey, ChainError> {
+        self.to_signer(derivation_path)?.public_key().await
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn sign(
         &self,
@@ -56,87 +63,242 @@ impl UserKey {
         chain_id: &str,
         derivation_path: &str,
     ) -> Result<RawTx, ChainError> {
-        let public_key = if account.pubkey.is_none() {
-            Some(self.public_key(derivation_path).await?)
-        } else {
-            account.pubkey
-        };
+        let signer = self.to_signer(derivation_path)?;
+        sign_tx(
+            signer.as_ref(),
+            msgs,
+            timeout_height,
+            memo,
+            account,
+            fee,
+            chain_id,
+        )
+        .await
+    }
+
+    pub async fn to_addr(
+        &self,
+        prefix: &str,
+        derivation_path: &str,
+    ) -> Result<Address, ChainError> {
+        ToSigner::to_addr(self, prefix, derivation_path).await
+    }
+
+    /// Signs `data` off-chain like Keplr's `signArbitrary`, see [`verify_arbitrary`](super::adr036::verify_arbitrary)
+    pub async fn sign_arbitrary(
+        &self,
+        chain_cfg: &ChainConfig,
+        data: &[u8],
+    ) -> Result<StdSignature, ChainError> {
+        let signer = self.to_chain_signer(chain_cfg)?;
+        sign_arbitrary(signer.as_ref(), &chain_cfg.prefix, data).await
+    }
+
+    /// Writes the key to `path` as an encrypted key file that `keys import` accepts
+    pub fn export_keystore(
+        &self,
+        path: impl AsRef<Path>,
+        derivation_path: &str,
+        passphrase: &str,
+    ) -> Result<(), ChainError> {
+        write_keystore(path, &self.secret(derivation_path)?, passphrase)
+    }
+
+    /// Derives the key once, see [`CachedSigner`]
+    pub fn cached_signer(&self, derivation_path: &str) -> Result<CachedSigner, ChainError> {
+        CachedSigner::new(self, derivation_path)
+    }
 
+    pub(crate) fn secret(&self, derivation_path: &str) -> Result<Vec<u8>, ChainError> {
         match &self.key {
-            Key::Raw(bytes) => {
-                let sign_doc = build_sign_doc(
-                    msgs,
-                    timeout_height,
-                    memo,
-                    &account,
-                    fee,
-                    public_key,
-                    chain_id,
-                )?;
-
-                let key = raw_bytes_to_signing_key(bytes)?;
-
-                let raw = sign_doc.sign(&key).map_err(ChainError::crypto)?;
-                Ok(raw.into())
-            }
+            Key::Raw(bytes) => Ok(bytes.clone()),
 
-            Key::Mnemonic(phrase) => {
-                let sign_doc = build_sign_doc(
-                    msgs,
-                    timeout_height,
-                    memo,
-                    &account,
-                    fee,
-                    public_key,
-                    chain_id,
-                )?;
-
-                let key = mnemonic_to_signing_key(phrase, derivation_path)?;
-
-                let raw = sign_doc.sign(&key).map_err(ChainError::crypto)?;
-                Ok(raw.into())
-            }
+            Key::Keystore(params) => read_keystore(&params.path, &params.passphrase.passphrase()?),
 
-            #[cfg(feature = "keyring")]
-            Key::Keyring(params) => {
-                let sign_doc = build_sign_doc(
-                    msgs,
-                    timeout_height,
-                    memo,
-                    &account,
-                    fee,
-                    public_key,
-                    chain_id,
-                )?;
-
-                let entry = Entry::new(&self.name, &params.user)?;
-                let key = mnemonic_to_signing_key(&entry.get_password()?, derivation_path)?;
-
-                let raw = sign_doc.sign(&key).map_err(ChainError::crypto)?;
-                Ok(raw.into())
-            }
+            _ => self.with_seed(|seed| seed_to_secret(seed, derivation_path)),
         }
     }
 
-    pub async fn to_addr(
+    /// Derives the keys at address indexes `indexes` of `chain_cfg.derivation_path`,
+    /// i.e. `m/44'/118'/0'/0/{index}`. Sign with one through
+    /// `to_signer_with_algorithm(&derived.derivation_path, chain_cfg.key_algorithm)`.
+    pub fn derive_many(
         &self,
-        prefix: &str,
-        derivation_path: &str,
-    ) -> Result<Address, ChainError> {
-        let account = self
-            .public_key(derivation_path)
-            .await?
-            .account_id(prefix)
-            .map_err(ChainError::crypto)?;
-        Ok(account.into())
+        chain_cfg: &ChainConfig,
+        indexes: impl IntoIterator<Item = u32>,
+    ) -> Result<Vec<DerivedKey>, ChainError> {
+        self.derive_paths(
+            chain_cfg,
+            indexes
+                .into_iter()
+                .map(|index| replace_path_component(&chain_cfg.derivation_path, 5, index, false)),
+        )
+    }
+
+    /// Derives the keys of the hardened accounts `accounts` of `chain_cfg.derivation_path`,
+    /// i.e. `m/44'/118'/{account}'/0/0`
+    pub fn derive_accounts(
+        &self,
+        chain_cfg: &ChainConfig,
+        accounts: impl IntoIterator<Item = u32>,
+    ) -> Result<Vec<DerivedKey>, ChainError> {
+        self.derive_paths(
+            chain_cfg,
+            accounts.into_iter().map(|account| {
+                replace_path_component(&chain_cfg.derivation_path, 3, account, true)
+            }),
+        )
+    }
+
+    fn derive_paths(
+        &self,
+        chain_cfg: &ChainConfig,
+        paths: impl Iterator<Item = Result<String, ChainError>>,
+    ) -> Result<Vec<DerivedKey>, ChainError> {
+        self.with_seed(|seed| {
+            paths
+                .map(|derivation_path| {
+                    let derivation_path = derivation_path?;
+                    let public_key = seed_to_public_key(seed, &derivation_path)?;
+                    let address = chain_cfg
+                        .key_algorithm
+                        .address(&public_key, &chain_cfg.prefix)?;
+
+                    Ok(DerivedKey {
+                        derivation_path,
+                        public_key,
+                        address,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Runs `f` with the BIP-39 seed of the key's mnemonic.
+    /// PBKDF2 runs once per `Key::Mnemonic`, keyring mnemonics are read and derived every time.
+    fn with_seed<T>(
+        &self,
+        f: impl FnOnce(&bip32::Seed) -> Result<T, ChainError>,
+    ) -> Result<T, ChainError> {
+        let phrase = match &self.key {
+            Key::Mnemonic(phrase) => phrase,
+            _ => return f(&mnemonic_to_seed(&self.mnemonic()?)?),
+        };
+
+        let mut cached = self.seed.0.lock().unwrap();
+        // `key` is public, so the cached seed is only used for the mnemonic it was derived from
+        if !matches!(&*cached, Some((cached_phrase, _)) if cached_phrase.as_str() == phrase) {
+            *cached = Some((Zeroizing::new(phrase.clone()), mnemonic_to_seed(phrase)?));
+        }
+        f(&cached.as_ref().expect("seed was just derived").1)
+    }
+
+    fn mnemonic(&self) -> Result<String, ChainError> {
+        match &self.key {
+            Key::Mnemonic(phrase) => Ok(phrase.clone()),
+
+            #[cfg(feature = "keyring")]
+            Key::Keyring(params) => Ok(Entry::new(&self.name, &params.user)?.get_password()?),
+
+            _ => Err(ChainError::NotMnemonic {
+                name: self.name.clone(),
+            }),
+        }
     }
 
     pub fn random_mnemonic(key_name: String) -> UserKey {
         let mnemonic = bip32::Mnemonic::random(OsRng, Default::default());
 
-        UserKey {
-            name: key_name,
-            key: Key::Mnemonic(mnemonic.phrase().to_string()),
+        UserKey::new(key_name, Key::Mnemonic(mnemonic.phrase().to_string()))
+    }
+}
+
+/// The seed of a mnemonic key and its mnemonic, both zeroized on drop.
+/// Not part of a key's identity, clones start without a seed.
+#[derive(Default)]
+struct SeedCache(Mutex<Option<(Zeroizing<String>, bip32::Seed)>>);
+
+impl Clone for SeedCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for SeedCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for SeedCache {}
+
+impl fmt::Debug for SeedCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SeedCache")
+    }
+}
+
+/// Public key and address at one HD path of a mnemonic key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DerivedKey {
+    pub derivation_path: String,
+    pub public_key: PublicKey,
+    pub address: Address,
+}
+
+/// Replaces component `position` of a `m/purpose'/coin_type'/account'/change/address_index` path
+fn replace_path_component(
+    derivation_path: &str,
+    position: usize,
+    value: u32,
+    hardened: bool,
+) -> Result<String, ChainError> {
+    let mut components: Vec<String> = derivation_path.split('/').map(String::from).collect();
+    if components.len() != 6 || components[0] != "m" {
+        return Err(ChainError::DerviationPath);
+    }
+
+    components[position] = if hardened {
+        format!("{value}'")
+    } else {
+        value.to_string()
+    };
+    Ok(components.join("/"))
+}
+
+impl ToSigner for UserKey {
+    fn to_signer(&self, derivation_path: &str) -> Result<Box<dyn Signer + '_>, ChainError> {
+        self.to_signer_with_algorithm(derivation_path, KeyAlgorithm::default())
+    }
+
+    fn to_signer_with_algorithm(
+        &self,
+        derivation_path: &str,
+        algorithm: KeyAlgorithm,
+    ) -> Result<Box<dyn Signer + '_>, ChainError> {
+        match &self.key {
+            Key::Raw(bytes) => Ok(Box::new(
+                LocalSigner::from_raw(bytes)?.with_algorithm(algorithm),
+            )),
+
+            Key::Mnemonic(_) => {
+                let secret =
+                    Zeroizing::new(self.with_seed(|seed| seed_to_secret(seed, derivation_path))?);
+                Ok(Box::new(
+                    LocalSigner::from_raw(&secret)?.with_algorithm(algorithm),
+                ))
+            }
+
+            #[cfg(feature = "keyring")]
+            Key::Keyring(params) => Ok(Box::new(
+                KeyringSigner::new(&self.name, &params.user, derivation_path)
+                    .with_algorithm(algorithm),
+            )),
+
+            Key::Keystore(params) => Ok(Box::new(
+                LocalSigner::from_keystore(&params.path, || params.passphrase.passphrase())?
+                    .with_algorithm(algorithm),
+            )),
         }
     }
 }
@@ -152,11 +314,14 @@ pub enum Key {
     /// DO NOT USE FOR MAINNET
     Mnemonic(String),
 
-    // TODO: Add Keyring password CRUD operations
-    /// Use OS Keyring to access private key.
+    /// Use OS Keyring to access private key, see `KeyringStore` to manage entries.
     /// Safe for testnet / mainnet.
     #[cfg(feature = "keyring")]
     Keyring(KeyringParams),
+
+    /// Encrypted key file, as written by `keys export`.
+    /// Safe for headless servers without an OS keyring.
+    Keystore(KeystoreParams),
     // TODO: Add ledger support(under a new ledger feature flag / Key variant)
 }
 
@@ -167,129 +332,167 @@ pub struct KeyringParams {
     pub user: String,
 }
 
-#[cfg(not(feature = "injective"))]
-fn mnemonic_to_signing_key(
-    mnemonic: &str,
-    derivation_path: &str,
-) -> Result<secp256k1::SigningKey, ChainError> {
-    let seed = bip32::Mnemonic::new(mnemonic, bip32::Language::English)
-        .map_err(|_| ChainError::Mnemonic)?
-        .to_seed("");
-
-    secp256k1::SigningKey::derive_from_path(
-        seed,
-        &derivation_path
-            .parse()
-            .map_err(|_| ChainError::DerviationPath)?,
-    )
-    .map_err(|_| ChainError::DerviationPath)
-}
-
-#[cfg(feature = "injective")]
-fn mnemonic_to_signing_key(
-    mnemonic: &str,
-    _derivation_path: &str,
-) -> Result<secp256k1::SigningKey, ChainError> {
-    use ethers_signers::{coins_bip39::English, MnemonicBuilder};
-
-    let wallet = MnemonicBuilder::<English>::default()
-        .phrase(mnemonic)
-        .index(0u32)
-        .unwrap()
-        .build()
-        .unwrap();
-    let bytes = wallet.signer().clone().to_bytes();
-    Ok(secp256k1::SigningKey::from_slice(&bytes).unwrap())
-}
-
-fn raw_bytes_to_signing_key(bytes: &[u8]) -> Result<secp256k1::SigningKey, ChainError> {
-    secp256k1::SigningKey::from_slice(bytes).map_err(ChainError::crypto)
-}
-
-fn build_sign_doc(
-    msgs: Vec<impl IntoAny>,
-    timeout_height: u64,
-    memo: &str,
-    account: &Account,
-    fee: Fee,
-    public_key: Option<PublicKey>,
-    chain_id: &str,
-) -> Result<SignDoc, ChainError> {
-    let timeout: Height = timeout_height.try_into()?;
-
-    let tx = Body::new(
-        msgs.into_iter()
-            .map(|m| m.into_any())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| ChainError::ProtoEncoding {
-                message: e.to_string(),
-            })?,
-        memo,
-        timeout,
-    );
-
-    // NOTE: if we are making requests in parallel with the same key, we need to serialize `account.sequence` to avoid errors
-    let auth_info =
-        SignerInfo::single_direct(public_key, account.sequence).auth_info(fee.try_into()?);
-
-    SignDoc::new(
-        &tx,
-        &auth_info,
-        &chain_id.parse().map_err(|_| ChainError::ChainId {
-            chain_id: chain_id.to_string(),
-        })?,
-        account.account_number,
-    )
-    .map_err(ChainError::proto_encoding)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct KeystoreParams {
+    /// Path of the ASCII armored key file
+    pub path: PathBuf,
+    /// Passphrase of the key file
+    pub passphrase: PassphraseSource,
 }
 
 #[cfg(test)]
 mod tests {
     use bech32::{self, ToBase32};
     use cosmrs::AccountId;
-    use ethers::signers::{coins_bip39::English, MnemonicBuilder, Signer};
+    use ethers::signers::{coins_bip39::English, MnemonicBuilder, Signer as _};
 
+    use crate::signing_key::algorithm::KeyAlgorithm;
     use crate::signing_key::key::{Key, UserKey};
+    use crate::signing_key::signer::{LocalSigner, Signer};
 
-    /// Attempt at getting injective key generation to work
-    #[ignore]
+    /// Keplr and the injective cli derive injective keys on the eth path, and hash the
+    /// uncompressed public key with keccak like ethereum does
     #[tokio::test]
-    async fn mnemonic_deterministic() {
-        let mnemonic = "cryptech dev key"; // for this test, the  Cryptech Dev Wallet was used
-        let addr = "inj1rmxnw6nmqqsnsk0d4c72v9794zfkgxkx23fart"; // address taken from injective and keplr
-        let index = 0u32;
+    async fn injective_mnemonic_address() {
+        // hardhat's default mnemonic, its first account is 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266
+        let mnemonic = "test test test test test test test test test test test junk";
+        let derivation_path = "m/44'/60'/0'/0/0";
+        let addr = "inj17w0adeg64ky0daxwd2ugyuneellmjgnxf5vkec";
 
-        println!("confirmed injective address from keplr: {}", addr);
+        let signer = LocalSigner::from_mnemonic(mnemonic, derivation_path)
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::EthSecp256k1);
+        assert_eq!(signer.address("inj").await.unwrap().as_ref(), addr);
 
-        let user_key = UserKey {
-            name: "test".to_string(),
-            key: Key::Mnemonic(mnemonic.to_string()),
-        };
+        let eth_addr = hex::decode("f39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap();
+        assert_eq!(AccountId::new("inj", &eth_addr).unwrap().to_string(), addr);
+
+        #[cfg(feature = "injective")]
+        {
+            let user_key = UserKey::new("test", Key::Mnemonic(mnemonic.to_string()));
+            let inj_addr = user_key.to_addr("inj", derivation_path).await.unwrap();
+            assert_eq!(inj_addr.as_ref(), addr);
+        }
+    }
 
-        let inj_addr = user_key.to_addr("inj", "test").await.unwrap();
+    #[tokio::test]
+    async fn eth_secp256k1_address() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let derivation_path = "m/44'/60'/0'/0/0";
+        // 0x9858EfFD232B4033E47d90003D41EC34EcaEda94 in bech32
+        let addr = "inj1npvwllfr9dqr8erajqqr6s0vxnk2ak55re90dz";
 
-        assert_eq!(inj_addr.as_ref(), addr);
+        let signer = LocalSigner::from_mnemonic(mnemonic, derivation_path)
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::EthSecp256k1);
+        assert_eq!(signer.address("inj").await.unwrap().as_ref(), addr);
+
+        #[cfg(feature = "injective")]
+        {
+            let user_key = UserKey::new("test", Key::Mnemonic(mnemonic.to_string()));
+            let inj_addr = user_key.to_addr("inj", derivation_path).await.unwrap();
+            assert_eq!(inj_addr.as_ref(), addr);
+        }
 
         let wallet = MnemonicBuilder::<English>::default()
             .phrase(mnemonic)
-            .index(index)
+            .index(0u32)
             .unwrap()
             .build()
             .unwrap();
-
         let inj_addr = bech32::encode(
             "inj",
             wallet.address().as_bytes().to_base32(),
             bech32::Variant::Bech32,
         )
         .unwrap();
+        assert_eq!(addr, inj_addr);
+    }
 
-        println!("inj_addr: {}", inj_addr);
-        assert_eq!(addr, inj_addr.to_string());
+    #[tokio::test]
+    async fn derive_many_matches_derivation_paths() {
+        let cfg = crate::config::cfg::ChainConfig::new(
+            "ujuno",
+            "juno",
+            "juno-1",
+            "m/44'/118'/0'/0/0",
+            0.025,
+            1.3,
+        );
+        let key = UserKey::random_mnemonic("test".to_string());
+
+        let derived = key.derive_many(&cfg, 0..3).unwrap();
+        assert_eq!(derived.len(), 3);
+        assert_eq!(derived[2].derivation_path, "m/44'/118'/0'/0/2");
+        assert_eq!(
+            derived[2].address,
+            key.to_addr("juno", "m/44'/118'/0'/0/2").await.unwrap()
+        );
+        assert_eq!(
+            derived[0].address,
+            key.to_addr("juno", &cfg.derivation_path).await.unwrap()
+        );
+
+        let accounts = key.derive_accounts(&cfg, [5]).unwrap();
+        assert_eq!(accounts[0].derivation_path, "m/44'/118'/5'/0/0");
+        assert_eq!(
+            accounts[0].public_key,
+            key.public_key("m/44'/118'/5'/0/0").await.unwrap()
+        );
+
+        let raw = UserKey::new("raw", Key::Raw(vec![7; 32]));
+        assert!(raw.derive_many(&cfg, 0..1).is_err());
+    }
 
-        let account = AccountId::new("inj", wallet.address().as_bytes()).unwrap();
+    #[tokio::test]
+    async fn seed_is_derived_once() {
+        use crate::chain::coin::Coin;
+        use crate::chain::fee::Fee;
+        use crate::modules::auth::model::Account;
+        use crate::modules::bank::model::SendRequest;
+        use crate::signing_key::signer::SEED_DERIVATIONS;
+
+        let derivation_path = "m/44'/118'/0'/0/0";
+        let derivations = || SEED_DERIVATIONS.with(|count| count.get());
+        let mut key = UserKey::random_mnemonic("test".to_string());
+        let start = derivations();
+
+        let address = key.to_addr("juno", derivation_path).await.unwrap();
+        key.public_key(derivation_path).await.unwrap();
+        let coin = Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        };
+        let req = SendRequest {
+            from: address.clone(),
+            to: address.clone(),
+            amounts: vec![coin.clone()],
+        };
+        let account = Account {
+            address,
+            pubkey: None,
+            account_number: 0,
+            sequence: 0,
+        };
+        for _ in 0..2 {
+            key.sign(
+                vec![req.clone()],
+                0,
+                "",
+                account.clone(),
+                Fee::new(coin.clone(), 100_000u64, None, None),
+                "juno-1",
+                derivation_path,
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(derivations() - start, 1);
 
-        println!("account address: {}", account);
-        assert_eq!(addr, account.to_string());
+        // the seed of a replaced mnemonic is not reused
+        key.key = UserKey::random_mnemonic("other".to_string()).key;
+        key.to_addr("juno", derivation_path).await.unwrap();
+        key.to_addr("juno", derivation_path).await.unwrap();
+        assert_eq!(derivations() - start, 2);
     }
 }
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use cosmrs::bip32;
+use cosmrs::crypto::PublicKey;
+use keyring::{Entry, Error as KeyringError};
+
+use crate::chain::error::ChainError;
+use crate::config::cfg::ChainConfig;
+use crate::modules::auth::model::Address;
+
+use super::key::{Key, KeyringParams, UserKey};
+use super::signer::mnemonic_to_public_key;
+
+/// Keyring service holding the index of key names for each keyring user
+const INDEX_SERVICE: &str = "cosm-utils";
+
+/// Manages the [`Key::Keyring`] mnemonics stored in the OS keyring for a single keyring `user`.
+/// OS keyrings cannot enumerate their entries, so key names are also kept in an index entry.
+#[derive(Debug)]
+pub struct KeyringStore {
+    pub user: String,
+    entries: Mutex<HashMap<String, Arc<Entry>>>,
+}
+
+impl KeyringStore {
+    pub fn new(user: impl Into<String>) -> Self {
+        Self {
+            user: user.into(),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores a freshly generated mnemonic under `name`
+    pub fn create(&self, name: &str) -> Result<UserKey, ChainError> {
+        let mnemonic = bip32::Mnemonic::random(
+            bip32::secp256k1::elliptic_curve::rand_core::OsRng,
+            Default::default(),
+        );
+        self.import(name, mnemonic.phrase())
+    }
+
+    /// Stores an existing mnemonic under `name`
+    pub fn import(&self, name: &str, mnemonic: &str) -> Result<UserKey, ChainError> {
+        bip32::Mnemonic::new(mnemonic, bip32::Language::English)
+            .map_err(|_| ChainError::Mnemonic)?;
+
+        let mut names = self.names()?;
+        if names.iter().any(|n| n == name) {
+            return Err(ChainError::KeyExists {
+                name: name.to_string(),
+            });
+        }
+
+        self.entry(name)?.set_password(mnemonic)?;
+        names.push(name.to_string());
+        self.save_names(&names)?;
+
+        Ok(self.user_key(name))
+    }
+
+    /// Returns every key stored for this user
+    pub fn list(&self) -> Result<Vec<UserKey>, ChainError> {
+        Ok(self
+            .names()?
+            .iter()
+            .map(|name| self.user_key(name))
+            .collect())
+    }
+
+    pub fn get(&self, name: &str) -> Result<UserKey, ChainError> {
+        self.ensure_exists(name)?;
+        Ok(self.user_key(name))
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<UserKey, ChainError> {
+        let mnemonic = self.mnemonic(name)?;
+        let key = self.import(new_name, &mnemonic)?;
+        self.delete(name)?;
+        Ok(key)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), ChainError> {
+        self.ensure_exists(name)?;
+
+        match self.entry(name)?.delete_password() {
+            Ok(()) | Err(KeyringError::NoEntry) => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.entries.lock().unwrap().remove(name);
+
+        let names: Vec<_> = self.names()?.into_iter().filter(|n| n != name).collect();
+        self.save_names(&names)
+    }
+
+    /// Derives the public key of `name` without handing out its mnemonic
+    pub fn public_key(&self, name: &str, derivation_path: &str) -> Result<PublicKey, ChainError> {
+        mnemonic_to_public_key(&self.mnemonic(name)?, derivation_path)
+    }
+
+    /// Derives the address of `name` on `chain_cfg`, with its derivation path and key algorithm,
+    /// without handing out its mnemonic
+    pub fn address(&self, name: &str, chain_cfg: &ChainConfig) -> Result<Address, ChainError> {
+        let public_key = self.public_key(name, &chain_cfg.derivation_path)?;
+        chain_cfg
+            .key_algorithm
+            .address(&public_key, &chain_cfg.prefix)
+    }
+
+    fn mnemonic(&self, name: &str) -> Result<String, ChainError> {
+        self.ensure_exists(name)?;
+        Ok(self.entry(name)?.get_password()?)
+    }
+
+    fn ensure_exists(&self, name: &str) -> Result<(), ChainError> {
+        if self.names()?.iter().any(|n| n == name) {
+            Ok(())
+        } else {
+            Err(ChainError::KeyNotFound {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    fn user_key(&self, name: &str) -> UserKey {
+        UserKey::new(
+            name,
+            Key::Keyring(KeyringParams {
+                user: self.user.clone(),
+            }),
+        )
+    }
+
+    fn names(&self) -> Result<Vec<String>, ChainError> {
+        match self.index()?.get_password() {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| ChainError::KeyringIndex {
+                message: e.to_string(),
+            }),
+            Err(KeyringError::NoEntry) => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_names(&self, names: &[String]) -> Result<(), ChainError> {
+        let json = serde_json::to_string(names).map_err(|e| ChainError::KeyringIndex {
+            message: e.to_string(),
+        })?;
+        Ok(self.index()?.set_password(&json)?)
+    }
+
+    fn index(&self) -> Result<Arc<Entry>, ChainError> {
+        self.cached_entry(INDEX_SERVICE)
+    }
+
+    /// `Key::Keyring` entries use the key name as the service, like `UserKey` does when signing
+    fn entry(&self, name: &str) -> Result<Arc<Entry>, ChainError> {
+        if name == INDEX_SERVICE {
+            return Err(ChainError::KeyExists {
+                name: name.to_string(),
+            });
+        }
+        self.cached_entry(name)
+    }
+
+    fn cached_entry(&self, service: &str) -> Result<Arc<Entry>, ChainError> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(service) {
+            return Ok(entry.clone());
+        }
+        let entry = Arc::new(Entry::new(service, &self.user)?);
+        entries.insert(service.to_string(), entry.clone());
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing_key::algorithm::KeyAlgorithm;
+    use crate::signing_key::signer::LocalSigner;
+    use crate::signing_key::signer::Signer;
+
+    const DERIVATION_PATH: &str = "m/44'/118'/0'/0/0";
+
+    #[tokio::test]
+    async fn keyring_crud() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let store = KeyringStore::new("alice");
+        assert!(store.list().unwrap().is_empty());
+
+        let created = store.create("hot").unwrap();
+        assert_eq!(created.name, "hot");
+        assert!(matches!(
+            store.create("hot"),
+            Err(ChainError::KeyExists { .. })
+        ));
+
+        let mnemonic = bip32::Mnemonic::random(
+            bip32::secp256k1::elliptic_curve::rand_core::OsRng,
+            Default::default(),
+        );
+        store.import("cold", mnemonic.phrase()).unwrap();
+        assert!(matches!(
+            store.import("bad", "not a mnemonic"),
+            Err(ChainError::Mnemonic)
+        ));
+
+        let names: Vec<_> = store.list().unwrap().into_iter().map(|k| k.name).collect();
+        assert_eq!(names, vec!["hot", "cold"]);
+
+        let expected = LocalSigner::from_mnemonic(mnemonic.phrase(), DERIVATION_PATH)
+            .unwrap()
+            .public_key()
+            .await
+            .unwrap();
+        assert_eq!(store.public_key("cold", DERIVATION_PATH).unwrap(), expected);
+        let cfg = ChainConfig::new("ujuno", "juno", "juno-1", DERIVATION_PATH, 0.025, 1.5);
+        let addr = store.address("cold", &cfg).unwrap();
+
+        let renamed = store.rename("cold", "archive").unwrap();
+        assert_eq!(renamed.name, "archive");
+        assert_eq!(store.address("archive", &cfg).unwrap(), addr);
+
+        // eth_secp256k1 chains hash the public key differently
+        let evmos = ChainConfig::new(
+            "aevmos",
+            "evmos",
+            "evmos_9001-2",
+            "m/44'/60'/0'/0/0",
+            25_000_000_000.0,
+            1.5,
+        )
+        .with_key_algorithm(KeyAlgorithm::EthSecp256k1);
+        let expected = LocalSigner::from_mnemonic(mnemonic.phrase(), "m/44'/60'/0'/0/0")
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::EthSecp256k1)
+            .address("evmos")
+            .await
+            .unwrap();
+        assert_eq!(store.address("archive", &evmos).unwrap(), expected);
+        assert!(matches!(
+            store.get("cold"),
+            Err(ChainError::KeyNotFound { .. })
+        ));
+
+        store.delete("hot").unwrap();
+        let names: Vec<_> = store.list().unwrap().into_iter().map(|k| k.name).collect();
+        assert_eq!(names, vec!["archive"]);
+        assert!(matches!(
+            store.delete("hot"),
+            Err(ChainError::KeyNotFound { .. })
+        ));
+    }
+}
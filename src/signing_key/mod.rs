@@ -1 +1,19 @@
+pub mod adr036;
+
+pub mod algorithm;
+
 pub mod key;
+
+pub mod signer;
+
+pub mod keystore;
+
+pub mod multisig;
+
+pub mod unsigned_tx;
+
+#[cfg(feature = "keyring")]
+pub mod keyring_store;
+
+#[cfg(feature = "remote-signer")]
+pub mod remote;
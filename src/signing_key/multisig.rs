@@ -0,0 +1,387 @@
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::crypto::multisig::v1beta1::{CompactBitArray, MultiSignature};
+use cosmrs::proto::cosmos::crypto::multisig::LegacyAminoPubKey;
+use cosmrs::proto::cosmos::crypto::secp256k1::PubKey as ProtoPubKey;
+use cosmrs::proto::cosmos::tx::v1beta1::{mode_info, AuthInfo, ModeInfo, SignerInfo, TxRaw};
+use cosmrs::proto::traits::Message;
+use cosmrs::tendermint::block::Height;
+use cosmrs::tx::{Body, SignMode};
+use cosmrs::{AccountId, Any};
+use sha2::{Digest, Sha256};
+
+use crate::chain::amino::StdSignDoc;
+use crate::chain::error::ChainError;
+use crate::chain::fee::Fee;
+use crate::chain::msg::IntoAny;
+use crate::chain::tx::RawTx;
+use crate::modules::auth::model::{Account, Address};
+
+use super::algorithm::KeyAlgorithm;
+use super::signer::Signer;
+
+const SECP256K1_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+pub(crate) const MULTISIG_TYPE_URL: &str = "/cosmos.crypto.multisig.LegacyAminoPubKey";
+
+/// Amino prefix of `tendermint/PubKeyMultisigThreshold`
+const MULTISIG_AMINO_PREFIX: [u8; 4] = [0x22, 0xc1, 0xf7, 0xe2];
+/// Amino prefix of `tendermint/PubKeySecp256k1`
+const SECP256K1_AMINO_PREFIX: [u8; 4] = [0xeb, 0x5a, 0xe9, 0x87];
+
+/// A `threshold`-of-`public_keys.len()` `LegacyAminoPubKey` multisig of secp256k1 keys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultisigKey {
+    pub threshold: u32,
+    pub public_keys: Vec<PublicKey>,
+}
+
+impl MultisigKey {
+    pub fn new(threshold: u32, public_keys: Vec<PublicKey>) -> Result<Self, ChainError> {
+        if threshold == 0 || threshold as usize > public_keys.len() {
+            return Err(ChainError::multisig(format!(
+                "threshold {threshold} is not between 1 and the {} member keys",
+                public_keys.len()
+            )));
+        }
+        if let Some(key) = public_keys
+            .iter()
+            .find(|key| key.type_url() != SECP256K1_TYPE_URL)
+        {
+            return Err(ChainError::multisig(format!(
+                "member key {} is not a secp256k1 key",
+                key.to_json()
+            )));
+        }
+
+        Ok(Self {
+            threshold,
+            public_keys,
+        })
+    }
+
+    /// Parses the `LegacyAminoPubKey` of a signer info
+    pub fn from_any(any: &Any) -> Result<Self, ChainError> {
+        let proto = LegacyAminoPubKey::decode(any.value.as_slice())
+            .map_err(ChainError::prost_proto_decoding)?;
+        let public_keys = proto
+            .public_keys
+            .into_iter()
+            .map(|key| PublicKey::try_from(key).map_err(ChainError::crypto))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::new(proto.threshold, public_keys)
+    }
+
+    /// Orders the member keys by address, like `keys add --multisig` does unless `--nosort` is passed.
+    /// The order changes the multisig address, so every co-signer has to use the same one.
+    pub fn sorted(mut self) -> Self {
+        // the prefix does not change the address bytes
+        self.public_keys
+            .sort_by_cached_key(|key| key.account_id("cosmos").map(|id| id.to_bytes()).ok());
+        self
+    }
+
+    /// Address of the multisig account, the first 20 bytes of the sha256 of its amino encoding
+    pub fn address(&self, prefix: &str) -> Result<Address, ChainError> {
+        let hash = Sha256::digest(self.amino_bytes());
+        let account = AccountId::new(prefix, &hash[..20]).map_err(ChainError::crypto)?;
+        Ok(account.into())
+    }
+
+    pub fn to_any(&self) -> Any {
+        Any {
+            type_url: MULTISIG_TYPE_URL.to_string(),
+            value: LegacyAminoPubKey {
+                threshold: self.threshold,
+                public_keys: self
+                    .public_keys
+                    .iter()
+                    .map(|key| Any {
+                        type_url: SECP256K1_TYPE_URL.to_string(),
+                        value: ProtoPubKey {
+                            key: key.to_bytes(),
+                        }
+                        .encode_to_vec(),
+                    })
+                    .collect(),
+            }
+            .encode_to_vec(),
+        }
+    }
+
+    fn amino_bytes(&self) -> Vec<u8> {
+        let mut bytes = MULTISIG_AMINO_PREFIX.to_vec();
+        // field 1, varint
+        bytes.push(0x08);
+        prost::encoding::encode_varint(self.threshold.into(), &mut bytes);
+        for key in &self.public_keys {
+            let mut key_bytes = SECP256K1_AMINO_PREFIX.to_vec();
+            let raw = key.to_bytes();
+            prost::encoding::encode_varint(raw.len() as u64, &mut key_bytes);
+            key_bytes.extend_from_slice(&raw);
+
+            // field 2, length delimited
+            bytes.push(0x12);
+            prost::encoding::encode_varint(key_bytes.len() as u64, &mut bytes);
+            bytes.extend_from_slice(&key_bytes);
+        }
+        bytes
+    }
+
+    fn position(&self, public_key: &PublicKey) -> Option<usize> {
+        self.public_keys.iter().position(|key| key == public_key)
+    }
+}
+
+/// Signature of one signer over a [`MultisigTx`] or an [`UnsignedTx`](super::unsigned_tx::UnsignedTx)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialSignature {
+    pub public_key: PublicKey,
+    pub signature: Vec<u8>,
+}
+
+/// An unsigned tx of a multisig account. Every co-signer builds the same one, signs it offline
+/// with [`MultisigTx::sign`], and any of them combines the signatures with [`MultisigTx::combine`].
+/// Multisig members sign in `SIGN_MODE_LEGACY_AMINO_JSON`, so every msg needs an amino encoding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultisigTx {
+    pub multisig: MultisigKey,
+    pub msgs: Vec<Any>,
+    pub timeout_height: u64,
+    pub memo: String,
+    pub account_number: u64,
+    pub sequence: u64,
+    pub fee: Fee,
+    pub chain_id: String,
+}
+
+impl MultisigTx {
+    pub fn new(
+        multisig: MultisigKey,
+        msgs: Vec<impl IntoAny>,
+        timeout_height: u64,
+        memo: &str,
+        account: &Account,
+        fee: Fee,
+        chain_id: &str,
+    ) -> Result<Self, ChainError> {
+        let msgs = msgs
+            .into_iter()
+            .map(|m| m.into_any())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ChainError::ProtoEncoding {
+                message: e.to_string(),
+            })?;
+
+        Ok(Self {
+            multisig,
+            msgs,
+            timeout_height,
+            memo: memo.to_string(),
+            account_number: account.account_number,
+            sequence: account.sequence,
+            fee,
+            chain_id: chain_id.to_string(),
+        })
+    }
+
+    /// The amino JSON `StdSignDoc` every member signs
+    pub fn sign_bytes(&self) -> Result<Vec<u8>, ChainError> {
+        StdSignDoc::new(
+            &self.msgs,
+            self.timeout_height,
+            &self.memo,
+            self.account_number,
+            self.sequence,
+            &self.fee,
+            &self.chain_id,
+        )?
+        .to_sign_bytes()
+    }
+
+    /// Signs the tx as one of the multisig members
+    pub async fn sign(&self, signer: &dyn Signer) -> Result<PartialSignature, ChainError> {
+        if !signer.sign_modes().contains(&SignMode::LegacyAminoJson) {
+            return Err(ChainError::UnsupportedSignMode {
+                mode: SignMode::LegacyAminoJson.as_str_name().to_string(),
+            });
+        }
+
+        let public_key = signer.public_key().await?;
+        if self.multisig.position(&public_key).is_none() {
+            return Err(ChainError::multisig(format!(
+                "{} is not a member of the multisig",
+                public_key.to_json()
+            )));
+        }
+
+        Ok(PartialSignature {
+            public_key,
+            signature: signer.sign(&self.sign_bytes()?).await?,
+        })
+    }
+
+    /// Combines at least `threshold` member signatures into a tx that can be broadcast
+    pub fn combine(
+        &self,
+        signatures: impl IntoIterator<Item = PartialSignature>,
+    ) -> Result<RawTx, ChainError> {
+        let sign_bytes = self.sign_bytes()?;
+        let mut signed: Vec<Option<Vec<u8>>> = vec![None; self.multisig.public_keys.len()];
+
+        for partial in signatures {
+            let position = self.multisig.position(&partial.public_key).ok_or_else(|| {
+                ChainError::multisig(format!(
+                    "{} is not a member of the multisig",
+                    partial.public_key.to_json()
+                ))
+            })?;
+            if !KeyAlgorithm::Secp256k1.verify(&partial.public_key, &sign_bytes, &partial.signature)
+            {
+                return Err(ChainError::multisig(format!(
+                    "invalid signature from {}",
+                    partial.public_key.to_json()
+                )));
+            }
+            signed[position] = Some(partial.signature);
+        }
+
+        let count = signed.iter().flatten().count();
+        if count < self.multisig.threshold as usize {
+            return Err(ChainError::multisig(format!(
+                "{count} of the {} required signatures",
+                self.multisig.threshold
+            )));
+        }
+
+        let single = ModeInfo {
+            sum: Some(mode_info::Sum::Single(mode_info::Single {
+                mode: SignMode::LegacyAminoJson.into(),
+            })),
+        };
+        let signer_info = SignerInfo {
+            public_key: Some(self.multisig.to_any()),
+            mode_info: Some(ModeInfo {
+                sum: Some(mode_info::Sum::Multi(mode_info::Multi {
+                    bitarray: Some(bit_array(&signed)),
+                    mode_infos: vec![single; count],
+                })),
+            }),
+            sequence: self.sequence,
+        };
+        let fee: cosmrs::tx::Fee = self.fee.clone().try_into()?;
+        let auth_info = AuthInfo {
+            signer_infos: vec![signer_info],
+            fee: Some(fee.into()),
+            ..Default::default()
+        };
+
+        let timeout: Height = self.timeout_height.try_into()?;
+        let body = Body::new(self.msgs.clone(), &self.memo, timeout);
+
+        Ok(TxRaw {
+            body_bytes: body.into_bytes().map_err(ChainError::proto_encoding)?,
+            auth_info_bytes: auth_info.encode_to_vec(),
+            signatures: vec![MultiSignature {
+                signatures: signed.into_iter().flatten().collect(),
+            }
+            .encode_to_vec()],
+        }
+        .into())
+    }
+}
+
+/// Bit `i` is set when member `i` signed, most significant bit first
+fn bit_array(signed: &[Option<Vec<u8>>]) -> CompactBitArray {
+    let mut elems = vec![0u8; (signed.len() + 7) / 8];
+    for (i, signature) in signed.iter().enumerate() {
+        if signature.is_some() {
+            elems[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+
+    CompactBitArray {
+        extra_bits_stored: (signed.len() % 8) as u32,
+        elems,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::coin::Coin;
+    use crate::modules::bank::model::SendRequest;
+    use crate::signing_key::signer::LocalSigner;
+
+    fn members() -> Vec<LocalSigner> {
+        (1..=3u8)
+            .map(|i| {
+                LocalSigner::from_raw(&[i; 32])
+                    .unwrap()
+                    .with_algorithm(KeyAlgorithm::Secp256k1)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn two_of_three_combines() {
+        let members = members();
+        let mut public_keys = vec![];
+        for member in &members {
+            public_keys.push(member.public_key().await.unwrap());
+        }
+        let multisig = MultisigKey::new(2, public_keys).unwrap();
+        assert!(MultisigKey::new(4, multisig.public_keys.clone()).is_err());
+
+        let amino = multisig.amino_bytes();
+        assert_eq!(amino[..6], [0x22, 0xc1, 0xf7, 0xe2, 0x08, 0x02]);
+        // 6 bytes of prefix and threshold, then 3 keys of 2 + 4 + 1 + 33 bytes
+        assert_eq!(amino.len(), 6 + 3 * 40);
+
+        let addr = multisig.address("cosmos").unwrap();
+        let coin = Coin {
+            denom: "uatom".parse().unwrap(),
+            amount: 100,
+        };
+        let msg = SendRequest {
+            from: addr.clone(),
+            to: addr.clone(),
+            amounts: vec![coin.clone()],
+        };
+        let account = Account {
+            address: addr,
+            pubkey: None,
+            account_number: 9,
+            sequence: 4,
+        };
+        let fee = Fee::new(coin, 200_000u64, None, None);
+        let tx = MultisigTx::new(multisig, vec![msg], 0, "", &account, fee, "cosmoshub-4").unwrap();
+
+        let first = tx.sign(&members[0]).await.unwrap();
+        let third = tx.sign(&members[2]).await.unwrap();
+        assert!(tx.combine([first.clone()]).is_err());
+
+        let raw: TxRaw = tx.combine([third, first]).unwrap().into();
+        let signatures = MultiSignature::decode(raw.signatures[0].as_slice()).unwrap();
+        assert_eq!(signatures.signatures.len(), 2);
+
+        let auth_info = AuthInfo::decode(raw.auth_info_bytes.as_slice()).unwrap();
+        let signer_info = &auth_info.signer_infos[0];
+        assert_eq!(signer_info.sequence, 4);
+        match signer_info
+            .mode_info
+            .as_ref()
+            .unwrap()
+            .sum
+            .as_ref()
+            .unwrap()
+        {
+            mode_info::Sum::Multi(multi) => {
+                let bitarray = multi.bitarray.as_ref().unwrap();
+                assert_eq!(bitarray.elems, vec![0b1010_0000]);
+                assert_eq!(bitarray.extra_bits_stored, 3);
+                assert_eq!(multi.mode_infos.len(), 2);
+            }
+            _ => panic!("expected a multi mode info"),
+        }
+    }
+}
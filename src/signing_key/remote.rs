@@ -0,0 +1,336 @@
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::tx::v1beta1::SignDoc;
+use cosmrs::proto::traits::Message;
+use hmac::{Hmac, Mac};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::OnceCell;
+
+use crate::chain::error::ChainError;
+
+use super::algorithm::secp256k1_public_key;
+use super::signer::Signer;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TIMESTAMP_HEADER: &str = "x-signer-timestamp";
+const MAC_HEADER: &str = "x-signer-mac";
+
+/// Requests whose timestamp is further than this from the server clock are rejected
+const MAX_CLOCK_SKEW_SECS: u64 = 30;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignRequest {
+    /// Protobuf encoded `SignDoc`
+    #[serde(with = "hex")]
+    pub sign_doc: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignResponse {
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+    /// Compressed secp256k1 public key
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PublicKeyResponse {
+    /// Compressed secp256k1 public key
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+}
+
+/// Signs by posting the `SignDoc` to a signing service, see [`serve`] for the server side.
+/// Every request is authenticated with an HMAC-SHA256 of the timestamp and body under a shared secret.
+pub struct RemoteSigner {
+    /// Base url of the signing service, e.g. `http://127.0.0.1:8080`
+    pub url: String,
+    secret: Vec<u8>,
+    client: reqwest::Client,
+    public_key: OnceCell<PublicKey>,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            secret: secret.into(),
+            client: reqwest::Client::new(),
+            public_key: OnceCell::new(),
+        }
+    }
+
+    async fn post<T, R>(&self, path: &str, req: &T) -> Result<R, ChainError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        let body = serde_json::to_vec(req).map_err(ChainError::remote_signer)?;
+        let timestamp = unix_timestamp();
+
+        let res = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                MAC_HEADER,
+                hex::encode(request_mac(&self.secret, timestamp, &body)),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(ChainError::remote_signer)?;
+
+        let status = res.status();
+        let bytes = res.bytes().await.map_err(ChainError::remote_signer)?;
+        if !status.is_success() {
+            return Err(ChainError::RemoteSigner {
+                message: format!("{status}: {}", String::from_utf8_lossy(&bytes)),
+            });
+        }
+
+        serde_json::from_slice(&bytes).map_err(ChainError::remote_signer)
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn public_key(&self) -> Result<PublicKey, ChainError> {
+        let public_key = self
+            .public_key
+            .get_or_try_init(|| async {
+                let res: PublicKeyResponse = self.post("/public_key", &()).await?;
+                parse_public_key(&res.public_key)
+            })
+            .await?;
+        Ok(*public_key)
+    }
+
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+        let res: SignResponse = self
+            .post(
+                "/sign",
+                &SignRequest {
+                    sign_doc: bytes.to_vec(),
+                },
+            )
+            .await?;
+
+        let public_key = parse_public_key(&res.public_key)?;
+        let expected = self.public_key.get_or_init(|| async { public_key }).await;
+        if *expected != public_key {
+            return Err(ChainError::RemoteSigner {
+                message: "signing service switched public keys".to_string(),
+            });
+        }
+
+        Ok(res.signature)
+    }
+}
+
+/// Runs the reference signing service on `listener` until the process exits.
+/// It answers `POST /public_key` and `POST /sign`, and only signs bytes that decode as a `SignDoc`.
+pub async fn serve(
+    listener: TcpListener,
+    signer: impl Signer + 'static,
+    secret: impl Into<Vec<u8>>,
+) -> Result<(), ChainError> {
+    let state = Arc::new(ServerState {
+        signer: Box::new(signer),
+        secret: secret.into(),
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(state.handle(req).await) }
+            }))
+        }
+    });
+
+    listener
+        .set_nonblocking(true)
+        .map_err(ChainError::remote_signer)?;
+    Server::from_tcp(listener)
+        .map_err(ChainError::remote_signer)?
+        .serve(make_service)
+        .await
+        .map_err(ChainError::remote_signer)
+}
+
+struct ServerState {
+    signer: Box<dyn Signer>,
+    secret: Vec<u8>,
+}
+
+impl ServerState {
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        match self.try_handle(req).await {
+            Ok(body) => response(StatusCode::OK, body),
+            Err((status, message)) => response(status, message.into_bytes()),
+        }
+    }
+
+    async fn try_handle(&self, req: Request<Body>) -> Result<Vec<u8>, (StatusCode, String)> {
+        if req.method() != Method::POST {
+            return Err((StatusCode::METHOD_NOT_ALLOWED, "expected POST".to_string()));
+        }
+
+        let path = req.uri().path().to_string();
+        let timestamp = header(&req, TIMESTAMP_HEADER)
+            .and_then(|t| t.parse::<u64>().ok())
+            .ok_or_else(|| unauthorized("missing timestamp"))?;
+        let mac = header(&req, MAC_HEADER)
+            .and_then(|mac| hex::decode(mac).ok())
+            .ok_or_else(|| unauthorized("missing mac"))?;
+
+        let body = hyper::body::to_bytes(req.into_body())
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        if unix_timestamp().abs_diff(timestamp) > MAX_CLOCK_SKEW_SECS {
+            return Err(unauthorized("stale timestamp"));
+        }
+        verify_mac(&self.secret, timestamp, &body, &mac).map_err(|_| unauthorized("bad mac"))?;
+
+        let public_key = self.signer.public_key().await.map_err(internal)?.to_bytes();
+
+        let res = match path.as_str() {
+            "/public_key" => serde_json::to_vec(&PublicKeyResponse { public_key }),
+            "/sign" => {
+                let req: SignRequest = serde_json::from_slice(&body)
+                    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+                SignDoc::decode(req.sign_doc.as_slice())
+                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid sign doc: {e}")))?;
+
+                let signature = self.signer.sign(&req.sign_doc).await.map_err(internal)?;
+                serde_json::to_vec(&SignResponse {
+                    signature,
+                    public_key,
+                })
+            }
+            _ => return Err((StatusCode::NOT_FOUND, format!("unknown path {path}"))),
+        };
+
+        res.map_err(internal)
+    }
+}
+
+fn header<'a>(req: &'a Request<Body>, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+fn unauthorized(message: &str) -> (StatusCode, String) {
+    (StatusCode::UNAUTHORIZED, message.to_string())
+}
+
+fn internal(e: impl std::fmt::Display) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn response(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+    let content_type = if status.is_success() {
+        "application/json"
+    } else {
+        "text/plain"
+    };
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .expect("static response parts are valid")
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn hmac(secret: &[u8], timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b"\n");
+    mac.update(body);
+    mac
+}
+
+fn request_mac(secret: &[u8], timestamp: u64, body: &[u8]) -> Vec<u8> {
+    hmac(secret, timestamp, body)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+fn verify_mac(
+    secret: &[u8],
+    timestamp: u64,
+    body: &[u8],
+    mac: &[u8],
+) -> Result<(), hmac::digest::MacError> {
+    hmac(secret, timestamp, body).verify_slice(mac)
+}
+
+fn parse_public_key(bytes: &[u8]) -> Result<PublicKey, ChainError> {
+    secp256k1_public_key(bytes).ok_or_else(|| ChainError::RemoteSigner {
+        message: format!("invalid public key {}", hex::encode(bytes)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing_key::signer::LocalSigner;
+
+    async fn spawn_server(secret: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let signer = LocalSigner::from_raw(&[7; 32]).unwrap();
+        tokio::spawn(serve(listener, signer, secret.as_bytes().to_vec()));
+        url
+    }
+
+    #[tokio::test]
+    async fn signs_over_http() {
+        let url = spawn_server("secret").await;
+        let local = LocalSigner::from_raw(&[7; 32]).unwrap();
+        let sign_doc = SignDoc {
+            body_bytes: vec![1, 2, 3],
+            auth_info_bytes: vec![4, 5, 6],
+            chain_id: "juno-1".to_string(),
+            account_number: 1,
+        }
+        .encode_to_vec();
+
+        let remote = RemoteSigner::new(&url, "secret");
+        assert_eq!(
+            remote.public_key().await.unwrap(),
+            local.public_key().await.unwrap()
+        );
+        // secp256k1 signatures are deterministic (RFC 6979)
+        assert_eq!(
+            remote.sign(&sign_doc).await.unwrap(),
+            local.sign(&sign_doc).await.unwrap()
+        );
+
+        let wrong_secret = RemoteSigner::new(&url, "guess");
+        assert!(matches!(
+            wrong_secret.sign(&sign_doc).await,
+            Err(ChainError::RemoteSigner { .. })
+        ));
+    }
+}
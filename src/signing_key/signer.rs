@@ -0,0 +1,596 @@
+use std::fmt;
+use std::path::Path;
+
+use async_trait::async_trait;
+use cosmrs::bip32;
+use cosmrs::bip32::secp256k1::ecdsa::{
+    signature::hazmat::PrehashSigner, Signature, SigningKey as EcdsaSigningKey,
+};
+use cosmrs::bip32::secp256k1::elliptic_curve::zeroize::Zeroizing;
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::tx::v1beta1::TxRaw;
+use cosmrs::tendermint::block::Height;
+use cosmrs::tx::{AuthInfo, Body, ModeInfo, SignDoc, SignMode, SignerInfo};
+use cosmrs::Any;
+
+#[cfg(feature = "keyring")]
+use keyring::Entry;
+
+use crate::chain::amino::StdSignDoc;
+use crate::chain::error::ChainError;
+use crate::chain::fee::Fee;
+use crate::chain::msg::IntoAny;
+use crate::chain::request::TxSignMode;
+use crate::chain::tx::RawTx;
+use crate::config::cfg::ChainConfig;
+use crate::modules::auth::model::{Account, Address};
+
+use super::algorithm::{secp256k1_public_key, KeyAlgorithm};
+use super::key::UserKey;
+use super::keystore::read_keystore;
+
+/// Produces transaction signatures for a single public key.
+/// Implement this to sign with keys held outside of the process, e.g. in a KMS or HSM.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    async fn public_key(&self) -> Result<PublicKey, ChainError>;
+
+    /// Signs the serialized `SignDoc` bytes, returning the raw signature
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError>;
+
+    /// Sign modes this signer is able to produce signatures for
+    fn sign_modes(&self) -> Vec<SignMode> {
+        vec![SignMode::Direct]
+    }
+
+    /// Decides how the public key is encoded in txs and how the address is derived
+    fn algorithm(&self) -> KeyAlgorithm {
+        KeyAlgorithm::Secp256k1
+    }
+
+    async fn address(&self, prefix: &str) -> Result<Address, ChainError> {
+        self.algorithm().address(&self.public_key().await?, prefix)
+    }
+}
+
+#[async_trait]
+impl<S> Signer for &S
+where
+    S: Signer + ?Sized,
+{
+    async fn public_key(&self) -> Result<PublicKey, ChainError> {
+        (**self).public_key().await
+    }
+
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+        (**self).sign(bytes).await
+    }
+
+    fn sign_modes(&self) -> Vec<SignMode> {
+        (**self).sign_modes()
+    }
+
+    fn algorithm(&self) -> KeyAlgorithm {
+        (**self).algorithm()
+    }
+}
+
+/// Anything that can hand out a [`Signer`] for a chain's derivation path.
+/// Every [`Signer`] implements this, ignoring the derivation path and key algorithm.
+#[async_trait]
+pub trait ToSigner: Send + Sync {
+    /// Signer using the default [`KeyAlgorithm`]
+    fn to_signer(&self, derivation_path: &str) -> Result<Box<dyn Signer + '_>, ChainError>;
+
+    /// Signer using `algorithm`. Signers with a fixed key type ignore `algorithm`.
+    fn to_signer_with_algorithm(
+        &self,
+        derivation_path: &str,
+        _algorithm: KeyAlgorithm,
+    ) -> Result<Box<dyn Signer + '_>, ChainError> {
+        self.to_signer(derivation_path)
+    }
+
+    /// Signer for the derivation path and key algorithm of `chain_cfg`
+    fn to_chain_signer(&self, chain_cfg: &ChainConfig) -> Result<Box<dyn Signer + '_>, ChainError> {
+        self.to_signer_with_algorithm(&chain_cfg.derivation_path, chain_cfg.key_algorithm)
+    }
+
+    async fn to_addr(&self, prefix: &str, derivation_path: &str) -> Result<Address, ChainError> {
+        self.to_signer(derivation_path)?.address(prefix).await
+    }
+
+    async fn to_chain_addr(&self, chain_cfg: &ChainConfig) -> Result<Address, ChainError> {
+        self.to_chain_signer(chain_cfg)?
+            .address(&chain_cfg.prefix)
+            .await
+    }
+}
+
+impl<S> ToSigner for S
+where
+    S: Signer,
+{
+    fn to_signer(&self, _derivation_path: &str) -> Result<Box<dyn Signer + '_>, ChainError> {
+        Ok(Box::new(self))
+    }
+}
+
+/// Signs with a secp256k1 key held in memory, which k256 zeroizes on drop
+pub struct LocalSigner {
+    key: EcdsaSigningKey,
+    algorithm: KeyAlgorithm,
+}
+
+impl LocalSigner {
+    pub fn new(key: EcdsaSigningKey) -> Self {
+        Self {
+            key,
+            algorithm: KeyAlgorithm::default(),
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn from_raw(bytes: &[u8]) -> Result<Self, ChainError> {
+        Ok(Self::new(raw_bytes_to_signing_key(bytes)?))
+    }
+
+    pub fn from_mnemonic(phrase: &str, derivation_path: &str) -> Result<Self, ChainError> {
+        Self::from_raw(&Zeroizing::new(mnemonic_to_secret(
+            phrase,
+            derivation_path,
+        )?))
+    }
+
+    /// Decrypts an armored key file, asking `passphrase` for its passphrase
+    pub fn from_keystore(
+        path: impl AsRef<Path>,
+        passphrase: impl FnOnce() -> Result<String, ChainError>,
+    ) -> Result<Self, ChainError> {
+        Self::from_raw(&Zeroizing::new(read_keystore(path, &passphrase()?)?))
+    }
+
+    fn verifying_public_key(&self) -> PublicKey {
+        secp256k1_public_key(self.key.verifying_key().to_encoded_point(true).as_bytes())
+            .expect("verifying keys are valid public keys")
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    async fn public_key(&self) -> Result<PublicKey, ChainError> {
+        Ok(self.verifying_public_key())
+    }
+
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+        let signature: Signature = self
+            .key
+            .sign_prehash(&self.algorithm.digest(bytes))
+            .map_err(|e| ChainError::Crypto {
+                message: e.to_string(),
+            })?;
+        Ok(signature
+            .normalize_s()
+            .unwrap_or(signature)
+            .to_bytes()
+            .to_vec())
+    }
+
+    fn sign_modes(&self) -> Vec<SignMode> {
+        vec![SignMode::Direct, SignMode::LegacyAminoJson]
+    }
+
+    fn algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+}
+
+/// A signing key derived once from a [`UserKey`] and kept in memory, so signing skips
+/// the seed derivation and keyring lookups. The secret is zeroized when this is dropped.
+pub struct CachedSigner {
+    signer: LocalSigner,
+    public_key: PublicKey,
+}
+
+impl CachedSigner {
+    pub fn new(key: &UserKey, derivation_path: &str) -> Result<Self, ChainError> {
+        let secret = Zeroizing::new(key.secret(derivation_path)?);
+        let signer = LocalSigner::from_raw(&secret)?;
+
+        Ok(Self {
+            public_key: signer.verifying_public_key(),
+            signer,
+        })
+    }
+
+    pub fn with_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.signer = self.signer.with_algorithm(algorithm);
+        self
+    }
+
+    pub fn address(&self, prefix: &str) -> Result<Address, ChainError> {
+        self.signer.algorithm.address(&self.public_key, prefix)
+    }
+}
+
+impl fmt::Debug for CachedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedSigner")
+            .field("public_key", &self.public_key)
+            .field("algorithm", &self.signer.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Signer for CachedSigner {
+    async fn public_key(&self) -> Result<PublicKey, ChainError> {
+        Ok(self.public_key)
+    }
+
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+        self.signer.sign(bytes).await
+    }
+
+    fn sign_modes(&self) -> Vec<SignMode> {
+        self.signer.sign_modes()
+    }
+
+    fn algorithm(&self) -> KeyAlgorithm {
+        self.signer.algorithm
+    }
+}
+
+/// Signs with a mnemonic stored in the OS keyring.
+/// The mnemonic is read from the keyring every time it is needed and never kept around.
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyringSigner {
+    /// keyring service name, the `UserKey` name
+    pub service: String,
+    pub user: String,
+    pub derivation_path: String,
+    pub algorithm: KeyAlgorithm,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringSigner {
+    pub fn new(service: &str, user: &str, derivation_path: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            user: user.to_string(),
+            derivation_path: derivation_path.to_string(),
+            algorithm: KeyAlgorithm::default(),
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    fn local_signer(&self) -> Result<LocalSigner, ChainError> {
+        let entry = Entry::new(&self.service, &self.user)?;
+        Ok(
+            LocalSigner::from_mnemonic(&entry.get_password()?, &self.derivation_path)?
+                .with_algorithm(self.algorithm),
+        )
+    }
+}
+
+#[cfg(feature = "keyring")]
+#[async_trait]
+impl Signer for KeyringSigner {
+    async fn public_key(&self) -> Result<PublicKey, ChainError> {
+        self.local_signer()?.public_key().await
+    }
+
+    async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+        self.local_signer()?.sign(bytes).await
+    }
+
+    fn sign_modes(&self) -> Vec<SignMode> {
+        vec![SignMode::Direct, SignMode::LegacyAminoJson]
+    }
+
+    fn algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+}
+
+/// Builds a `SIGN_MODE_DIRECT` sign doc for `msgs` and signs it with `signer`
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_tx(
+    signer: &dyn Signer,
+    msgs: Vec<impl IntoAny>,
+    timeout_height: u64,
+    memo: &str,
+    account: Account,
+    fee: Fee,
+    chain_id: &str,
+) -> Result<RawTx, ChainError> {
+    sign_tx_with_mode(
+        signer,
+        TxSignMode::Direct,
+        msgs,
+        timeout_height,
+        memo,
+        account,
+        fee,
+        chain_id,
+    )
+    .await
+}
+
+/// Signs `msgs` with `signer` in `sign_mode`
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_tx_with_mode(
+    signer: &dyn Signer,
+    sign_mode: TxSignMode,
+    msgs: Vec<impl IntoAny>,
+    timeout_height: u64,
+    memo: &str,
+    account: Account,
+    fee: Fee,
+    chain_id: &str,
+) -> Result<RawTx, ChainError> {
+    let mode = SignMode::from(sign_mode);
+    if !signer.sign_modes().contains(&mode) {
+        return Err(ChainError::UnsupportedSignMode {
+            mode: mode.as_str_name().to_string(),
+        });
+    }
+
+    let msgs = msgs
+        .into_iter()
+        .map(|m| m.into_any())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ChainError::ProtoEncoding {
+            message: e.to_string(),
+        })?;
+
+    let (body, auth_info) = tx_parts(
+        signer,
+        sign_mode,
+        &msgs,
+        timeout_height,
+        memo,
+        &account,
+        &fee,
+    )
+    .await?;
+
+    let sign_bytes = match sign_mode {
+        TxSignMode::Direct => SignDoc::new(
+            &body,
+            &auth_info,
+            &chain_id.parse().map_err(|_| ChainError::ChainId {
+                chain_id: chain_id.to_string(),
+            })?,
+            account.account_number,
+        )
+        .and_then(SignDoc::into_bytes)
+        .map_err(ChainError::proto_encoding)?,
+
+        TxSignMode::LegacyAminoJson => StdSignDoc::new(
+            &msgs,
+            timeout_height,
+            memo,
+            account.account_number,
+            account.sequence,
+            &fee,
+            chain_id,
+        )?
+        .to_sign_bytes()?,
+    };
+
+    let signature = signer.sign(&sign_bytes).await?;
+
+    Ok(TxRaw {
+        body_bytes: body.into_bytes().map_err(ChainError::proto_encoding)?,
+        auth_info_bytes: auth_info.into_bytes().map_err(ChainError::proto_encoding)?,
+        signatures: vec![signature],
+    }
+    .into())
+}
+
+/// The tx [`sign_tx_with_mode`] would sign, with an empty signature, to be simulated.
+/// The ante handler charges for the size of the missing signature itself,
+/// so the simulation uses as much gas as the signed tx.
+#[allow(clippy::too_many_arguments)]
+pub async fn simulation_tx(
+    signer: &dyn Signer,
+    sign_mode: TxSignMode,
+    msgs: &[Any],
+    timeout_height: u64,
+    memo: &str,
+    account: &Account,
+    fee: &Fee,
+) -> Result<RawTx, ChainError> {
+    let (body, auth_info) =
+        tx_parts(signer, sign_mode, msgs, timeout_height, memo, account, fee).await?;
+
+    Ok(TxRaw {
+        body_bytes: body.into_bytes().map_err(ChainError::proto_encoding)?,
+        auth_info_bytes: auth_info.into_bytes().map_err(ChainError::proto_encoding)?,
+        signatures: vec![vec![]],
+    }
+    .into())
+}
+
+// the body and auth info of a tx of a single signer
+#[allow(clippy::too_many_arguments)]
+async fn tx_parts(
+    signer: &dyn Signer,
+    sign_mode: TxSignMode,
+    msgs: &[Any],
+    timeout_height: u64,
+    memo: &str,
+    account: &Account,
+    fee: &Fee,
+) -> Result<(Body, AuthInfo), ChainError> {
+    let public_key = match account.pubkey {
+        Some(public_key) => public_key,
+        None => signer.public_key().await?,
+    };
+
+    // parallel requests with the same key need distinct sequences, see `SequenceManager`
+    let auth_info = SignerInfo {
+        public_key: Some(signer.algorithm().signer_public_key(public_key)),
+        mode_info: ModeInfo::single(sign_mode.into()),
+        sequence: account.sequence,
+    }
+    .auth_info(fee.clone().try_into()?);
+
+    let timeout: Height = timeout_height.try_into()?;
+    Ok((Body::new(msgs.to_vec(), memo, timeout), auth_info))
+}
+
+pub(crate) fn mnemonic_to_public_key(
+    mnemonic: &str,
+    derivation_path: &str,
+) -> Result<PublicKey, ChainError> {
+    seed_to_public_key(&mnemonic_to_seed(mnemonic)?, derivation_path)
+}
+
+/// Derives the public key at `derivation_path` from an already computed seed,
+/// so deriving many keys of one mnemonic runs PBKDF2 only once
+pub(crate) fn seed_to_public_key(
+    seed: &bip32::Seed,
+    derivation_path: &str,
+) -> Result<PublicKey, ChainError> {
+    let secret = Zeroizing::new(seed_to_secret(seed, derivation_path)?);
+    Ok(LocalSigner::from_raw(&secret)?.verifying_public_key())
+}
+
+/// Derives the raw secp256k1 secret of `mnemonic` at `derivation_path`
+pub(crate) fn mnemonic_to_secret(
+    mnemonic: &str,
+    derivation_path: &str,
+) -> Result<Vec<u8>, ChainError> {
+    seed_to_secret(&mnemonic_to_seed(mnemonic)?, derivation_path)
+}
+
+#[cfg(test)]
+thread_local! {
+    /// PBKDF2 runs on the current thread, to check that seeds are not derived over and over
+    pub(crate) static SEED_DERIVATIONS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// The BIP-39 seed of `mnemonic`, which is zeroized on drop
+pub(crate) fn mnemonic_to_seed(mnemonic: &str) -> Result<bip32::Seed, ChainError> {
+    #[cfg(test)]
+    SEED_DERIVATIONS.with(|count| count.set(count.get() + 1));
+    Ok(bip32::Mnemonic::new(mnemonic, bip32::Language::English)
+        .map_err(|_| ChainError::Mnemonic)?
+        .to_seed(""))
+}
+
+pub(crate) fn seed_to_secret(
+    seed: &bip32::Seed,
+    derivation_path: &str,
+) -> Result<Vec<u8>, ChainError> {
+    let xprv = bip32::XPrv::derive_from_path(
+        seed,
+        &derivation_path
+            .parse()
+            .map_err(|_| ChainError::DerviationPath)?,
+    )
+    .map_err(|_| ChainError::DerviationPath)?;
+
+    Ok(xprv.private_key().to_bytes().to_vec())
+}
+
+pub(crate) fn raw_bytes_to_signing_key(bytes: &[u8]) -> Result<EcdsaSigningKey, ChainError> {
+    EcdsaSigningKey::from_slice(bytes).map_err(|e| ChainError::Crypto {
+        message: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::coin::Coin;
+    use crate::modules::bank::model::SendRequest;
+
+    struct CountingSigner {
+        inner: LocalSigner,
+        signed: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Signer for CountingSigner {
+        async fn public_key(&self) -> Result<PublicKey, ChainError> {
+            self.inner.public_key().await
+        }
+
+        async fn sign(&self, bytes: &[u8]) -> Result<Vec<u8>, ChainError> {
+            self.signed
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.sign(bytes).await
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_signer_signs_tx() {
+        let signer = CountingSigner {
+            inner: LocalSigner::from_raw(&[7; 32]).unwrap(),
+            signed: Default::default(),
+        };
+        let public_key = signer.public_key().await.unwrap();
+        let addr = signer.to_addr("juno", "ignored").await.unwrap();
+        assert_eq!(addr, public_key.account_id("juno").unwrap().into());
+
+        let account = Account {
+            address: addr.clone(),
+            pubkey: None,
+            account_number: 3,
+            sequence: 1,
+        };
+        let coin = Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        };
+        let msg = SendRequest {
+            from: addr.clone(),
+            to: addr,
+            amounts: vec![coin.clone()],
+        };
+        let fee = Fee::new(coin, 100_000u64, None, None);
+        let raw: TxRaw = sign_tx(&signer, vec![msg], 0, "memo", account, fee, "juno-1")
+            .await
+            .unwrap()
+            .into();
+
+        assert_eq!(signer.signed.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(raw.signatures.len(), 1);
+        assert_eq!(raw.signatures[0].len(), 64);
+    }
+
+    #[tokio::test]
+    async fn cached_signer_matches_user_key() {
+        let derivation_path = "m/44'/118'/0'/0/0";
+        let key = UserKey::random_mnemonic("test".to_string());
+        let cached = key.cached_signer(derivation_path).unwrap();
+        let local = key.to_signer(derivation_path).unwrap();
+
+        assert_eq!(
+            cached.public_key().await.unwrap(),
+            key.public_key(derivation_path).await.unwrap()
+        );
+        assert_eq!(
+            cached.address("juno").unwrap(),
+            key.to_addr("juno", derivation_path).await.unwrap()
+        );
+        assert_eq!(
+            cached.sign(b"sign doc").await.unwrap(),
+            local.sign(b"sign doc").await.unwrap()
+        );
+        // the debug output never contains the secret
+        assert!(format!("{cached:?}").starts_with("CachedSigner { public_key: "));
+    }
+}
@@ -0,0 +1,520 @@
+use cosmrs::crypto::PublicKey;
+use cosmrs::proto::cosmos::tx::v1beta1::{AuthInfo, SignDoc, TxBody, TxRaw};
+use cosmrs::proto::tendermint::google::protobuf::Timestamp;
+use cosmrs::proto::traits::Message;
+use cosmrs::tendermint::{block::Height, Time};
+use cosmrs::tx::{Body, ModeInfo, SignMode, SignerInfo};
+use cosmrs::Any;
+
+use crate::chain::amino::StdSignDoc;
+use crate::chain::error::ChainError;
+use crate::chain::fee::Fee;
+use crate::chain::msg::IntoAny;
+use crate::chain::request::TxSignMode;
+use crate::chain::signers::tx_signers_with_default;
+use crate::chain::tx::RawTx;
+use crate::chain::tx_json::TxJson;
+use crate::modules::auth::model::{Account, Address};
+
+use super::algorithm::KeyAlgorithm;
+use super::multisig::PartialSignature;
+use super::signer::Signer;
+
+/// A signer of an [`UnsignedTx`]. The public key and sequence of every signer are part of the
+/// signed auth info, so they have to be known before anyone signs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxSigner {
+    pub address: Address,
+    pub public_key: PublicKey,
+    pub algorithm: KeyAlgorithm,
+    pub account_number: u64,
+    pub sequence: u64,
+}
+
+impl TxSigner {
+    pub async fn new(signer: &dyn Signer, account: &Account) -> Result<Self, ChainError> {
+        let public_key = match account.pubkey {
+            Some(public_key) => public_key,
+            None => signer.public_key().await?,
+        };
+
+        Ok(Self {
+            address: account.address.clone(),
+            public_key,
+            algorithm: signer.algorithm(),
+            account_number: account.account_number,
+            sequence: account.sequence,
+        })
+    }
+
+    /// A signer known only by its public key, with the account number and sequence supplied by the
+    /// caller, so txs can be prepared without querying the chain
+    pub fn from_public_key(
+        public_key: PublicKey,
+        algorithm: KeyAlgorithm,
+        prefix: &str,
+        account_number: u64,
+        sequence: u64,
+    ) -> Result<Self, ChainError> {
+        Ok(Self {
+            address: algorithm.address(&public_key, prefix)?,
+            public_key,
+            algorithm,
+            account_number,
+            sequence,
+        })
+    }
+}
+
+/// A tx whose msgs need the signatures of one or more accounts, for example a fee payer and a sender.
+/// Signatures are collected with [`UnsignedTx::sign`] in process, or by handing
+/// [`UnsignedTx::sign_bytes`] to each key holder, and then put together with [`UnsignedTx::combine`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsignedTx {
+    pub msgs: Vec<Any>,
+    pub timeout_height: u64,
+    pub memo: String,
+    pub fee: Fee,
+    pub chain_id: String,
+    pub sign_mode: TxSignMode,
+    pub extension_options: Vec<Any>,
+    pub non_critical_extension_options: Vec<Any>,
+    /// `timeout_timestamp` of the tx body, which only cosmos-sdk v0.53 and newer chains know of.
+    /// Older chains reject txs that set it. [`TxJson`] and [`RawTx::decode`] drop it.
+    pub timeout_timestamp: Option<Time>,
+    /// In the order the cosmos-sdk expects signer infos and signatures, see
+    /// [`tx_signers`](crate::chain::signers::tx_signers)
+    pub signers: Vec<TxSigner>,
+}
+
+impl UnsignedTx {
+    /// Orders `signers` like the chain requires, failing when one is missing or not needed.
+    /// Msgs without registered signers are only accepted with a single signer, who signs them.
+    pub fn new(
+        msgs: Vec<impl IntoAny>,
+        timeout_height: u64,
+        memo: &str,
+        fee: Fee,
+        chain_id: &str,
+        signers: Vec<TxSigner>,
+    ) -> Result<Self, ChainError> {
+        let msgs = msgs
+            .into_iter()
+            .map(|m| m.into_any())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ChainError::ProtoEncoding {
+                message: e.to_string(),
+            })?;
+
+        Self::from_any_msgs(msgs, timeout_height, memo, fee, chain_id, signers)
+    }
+
+    /// Reads a tx printed by `gaiad tx ... --generate-only` or [`UnsignedTx::to_json`].
+    /// The JSON carries no account numbers, sequences or public keys, so `signers` supplies them.
+    pub fn from_json(
+        json: &TxJson,
+        chain_id: &str,
+        signers: Vec<TxSigner>,
+    ) -> Result<Self, ChainError> {
+        let raw: TxRaw = json.to_raw()?.into();
+        let body =
+            TxBody::decode(raw.body_bytes.as_slice()).map_err(ChainError::prost_proto_decoding)?;
+        let auth_info = AuthInfo::decode(raw.auth_info_bytes.as_slice())
+            .map_err(ChainError::prost_proto_decoding)?;
+        let fee: Fee = cosmrs::tx::Fee::try_from(auth_info.fee.unwrap_or_default())?.try_into()?;
+
+        Ok(Self::from_any_msgs(
+            body.messages,
+            body.timeout_height,
+            &body.memo,
+            fee,
+            chain_id,
+            signers,
+        )?
+        .with_extension_options(body.extension_options, body.non_critical_extension_options))
+    }
+
+    pub(crate) fn from_any_msgs(
+        msgs: Vec<Any>,
+        timeout_height: u64,
+        memo: &str,
+        fee: Fee,
+        chain_id: &str,
+        mut signers: Vec<TxSigner>,
+    ) -> Result<Self, ChainError> {
+        // a single signer signs every msg whose signers are not registered
+        let default = match signers.as_slice() {
+            [signer] => Some(&signer.address),
+            _ => None,
+        };
+        let required = tx_signers_with_default(&msgs, &fee, default)?;
+        if let Some(extra) = signers.iter().find(|s| !required.contains(&s.address)) {
+            return Err(ChainError::TxSigners {
+                message: format!("{} does not sign any msg or the fee", extra.address),
+            });
+        }
+        let mut ordered = Vec::with_capacity(required.len());
+        for address in required {
+            let position = signers
+                .iter()
+                .position(|s| s.address == address)
+                .ok_or_else(|| ChainError::TxSigners {
+                    message: format!("missing signer {address}"),
+                })?;
+            ordered.push(signers.swap_remove(position));
+        }
+
+        Ok(Self {
+            msgs,
+            timeout_height,
+            memo: memo.to_string(),
+            fee,
+            chain_id: chain_id.to_string(),
+            sign_mode: TxSignMode::Direct,
+            extension_options: vec![],
+            non_critical_extension_options: vec![],
+            timeout_timestamp: None,
+            signers: ordered,
+        })
+    }
+
+    pub fn with_sign_mode(mut self, sign_mode: TxSignMode) -> Self {
+        self.sign_mode = sign_mode;
+        self
+    }
+
+    pub fn with_extension_options(
+        mut self,
+        extension_options: Vec<Any>,
+        non_critical_extension_options: Vec<Any>,
+    ) -> Self {
+        self.extension_options = extension_options;
+        self.non_critical_extension_options = non_critical_extension_options;
+        self
+    }
+
+    pub fn with_timeout_timestamp(mut self, timeout_timestamp: Option<Time>) -> Self {
+        self.timeout_timestamp = timeout_timestamp;
+        self
+    }
+
+    pub fn body_bytes(&self) -> Result<Vec<u8>, ChainError> {
+        let timeout: Height = self.timeout_height.try_into()?;
+        let mut bytes = Body::new(self.msgs.clone(), &self.memo, timeout)
+            .into_bytes()
+            .map_err(ChainError::proto_encoding)?;
+
+        // the proto `TxBody` of cosmrs has no `timeout_timestamp` (field 5) yet, so it is encoded
+        // by hand, between the fields above and the extension options (fields 1023 and 2047)
+        if let Some(timeout_timestamp) = self.timeout_timestamp {
+            prost::encoding::message::encode(5, &Timestamp::from(timeout_timestamp), &mut bytes);
+        }
+        bytes.extend(
+            TxBody {
+                extension_options: self.extension_options.clone(),
+                non_critical_extension_options: self.non_critical_extension_options.clone(),
+                ..Default::default()
+            }
+            .encode_to_vec(),
+        );
+
+        Ok(bytes)
+    }
+
+    pub fn auth_info_bytes(&self) -> Result<Vec<u8>, ChainError> {
+        Ok(self.auth_info()?.encode_to_vec())
+    }
+
+    /// The tx as `gaiad tx ... --generate-only` prints it, to be signed elsewhere.
+    /// Like gaiad's, it has no signer infos.
+    pub fn to_json(&self) -> Result<TxJson, ChainError> {
+        let auth_info = AuthInfo {
+            signer_infos: vec![],
+            ..self.auth_info()?
+        };
+
+        TxJson::from_raw(
+            &TxRaw {
+                body_bytes: self.body_bytes()?,
+                auth_info_bytes: auth_info.encode_to_vec(),
+                signatures: vec![],
+            }
+            .into(),
+        )
+    }
+
+    fn auth_info(&self) -> Result<AuthInfo, ChainError> {
+        let mode_info = ModeInfo::single(self.sign_mode.into());
+        let fee: cosmrs::tx::Fee = self.fee.clone().try_into()?;
+
+        Ok(AuthInfo {
+            signer_infos: self
+                .signers
+                .iter()
+                .map(|signer| {
+                    SignerInfo {
+                        public_key: Some(signer.algorithm.signer_public_key(signer.public_key)),
+                        mode_info: mode_info.clone(),
+                        sequence: signer.sequence,
+                    }
+                    .into()
+                })
+                .collect(),
+            fee: Some(fee.into()),
+            ..Default::default()
+        })
+    }
+
+    /// The bytes `address` signs. Key holders outside this process return `Signer::sign` of them.
+    pub fn sign_bytes(&self, address: &Address) -> Result<Vec<u8>, ChainError> {
+        let signer = self.signer(address)?;
+
+        match self.sign_mode {
+            TxSignMode::Direct => Ok(SignDoc {
+                body_bytes: self.body_bytes()?,
+                auth_info_bytes: self.auth_info_bytes()?,
+                chain_id: self.chain_id.clone(),
+                account_number: signer.account_number,
+            }
+            .encode_to_vec()),
+
+            // like the cosmos-sdk, whose amino JSON sign doc has no room for them
+            TxSignMode::LegacyAminoJson
+                if !self.extension_options.is_empty()
+                    || !self.non_critical_extension_options.is_empty()
+                    || self.timeout_timestamp.is_some() =>
+            {
+                Err(ChainError::amino_json(
+                    "SIGN_MODE_LEGACY_AMINO_JSON does not support extension options or a timeout timestamp",
+                ))
+            }
+
+            TxSignMode::LegacyAminoJson => StdSignDoc::new(
+                &self.msgs,
+                self.timeout_height,
+                &self.memo,
+                signer.account_number,
+                signer.sequence,
+                &self.fee,
+                &self.chain_id,
+            )?
+            .to_sign_bytes(),
+        }
+    }
+
+    /// Signs the tx as whichever of its signers `signer` holds the key of
+    pub async fn sign(&self, signer: &dyn Signer) -> Result<PartialSignature, ChainError> {
+        let mode = SignMode::from(self.sign_mode);
+        if !signer.sign_modes().contains(&mode) {
+            return Err(ChainError::UnsupportedSignMode {
+                mode: mode.as_str_name().to_string(),
+            });
+        }
+
+        let public_key = signer.public_key().await?;
+        let tx_signer = self
+            .signers
+            .iter()
+            .find(|s| s.public_key == public_key && s.algorithm == signer.algorithm())
+            .ok_or_else(|| ChainError::TxSigners {
+                message: format!("{} is not a signer of this tx", public_key.to_json()),
+            })?;
+
+        Ok(PartialSignature {
+            public_key,
+            signature: signer.sign(&self.sign_bytes(&tx_signer.address)?).await?,
+        })
+    }
+
+    /// Puts the signatures of every signer in order, ready to be broadcast
+    pub fn combine(
+        &self,
+        signatures: impl IntoIterator<Item = PartialSignature>,
+    ) -> Result<RawTx, ChainError> {
+        let mut signed: Vec<Option<Vec<u8>>> = vec![None; self.signers.len()];
+
+        for partial in signatures {
+            let position = self
+                .signers
+                .iter()
+                .position(|s| s.public_key == partial.public_key)
+                .ok_or_else(|| ChainError::TxSigners {
+                    message: format!(
+                        "{} is not a signer of this tx",
+                        partial.public_key.to_json()
+                    ),
+                })?;
+            let signer = &self.signers[position];
+
+            if !signer.algorithm.verify(
+                &signer.public_key,
+                &self.sign_bytes(&signer.address)?,
+                &partial.signature,
+            ) {
+                return Err(ChainError::TxSigners {
+                    message: format!("invalid signature from {}", signer.address),
+                });
+            }
+            signed[position] = Some(partial.signature);
+        }
+
+        let signatures = signed
+            .into_iter()
+            .zip(&self.signers)
+            .map(|(signature, signer)| {
+                signature.ok_or_else(|| ChainError::TxSigners {
+                    message: format!("missing signature from {}", signer.address),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TxRaw {
+            body_bytes: self.body_bytes()?,
+            auth_info_bytes: self.auth_info_bytes()?,
+            signatures,
+        }
+        .into())
+    }
+
+    /// Like [`UnsignedTx::combine`], as the JSON `gaiad tx sign` prints
+    pub fn combine_json(
+        &self,
+        signatures: impl IntoIterator<Item = PartialSignature>,
+    ) -> Result<TxJson, ChainError> {
+        TxJson::from_raw(&self.combine(signatures)?)
+    }
+
+    /// The tx with empty signatures, which is all the simulate endpoint needs
+    pub(crate) fn simulation_tx(&self) -> Result<RawTx, ChainError> {
+        Ok(TxRaw {
+            body_bytes: self.body_bytes()?,
+            auth_info_bytes: self.auth_info_bytes()?,
+            signatures: vec![vec![]; self.signers.len()],
+        }
+        .into())
+    }
+
+    fn signer(&self, address: &Address) -> Result<&TxSigner, ChainError> {
+        self.signers
+            .iter()
+            .find(|s| &s.address == address)
+            .ok_or_else(|| ChainError::TxSigners {
+                message: format!("{address} is not a signer of this tx"),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::coin::Coin;
+    use crate::modules::bank::model::SendRequest;
+    use crate::signing_key::signer::LocalSigner;
+
+    async fn tx_signer(signer: &LocalSigner, account_number: u64) -> TxSigner {
+        let account = Account {
+            address: signer.address("juno").await.unwrap(),
+            pubkey: None,
+            account_number,
+            sequence: 0,
+        };
+        TxSigner::new(signer, &account).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn fee_payer_and_sender_sign() {
+        let sender = LocalSigner::from_raw(&[1; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let payer = LocalSigner::from_raw(&[2; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let sender_signer = tx_signer(&sender, 1).await;
+        let payer_signer = tx_signer(&payer, 2).await;
+
+        let coin = Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        };
+        let msg = SendRequest {
+            from: sender_signer.address.clone(),
+            to: payer_signer.address.clone(),
+            amounts: vec![coin.clone()],
+        };
+        let fee = Fee::new(coin, 100_000u64, Some(payer_signer.address.clone()), None);
+
+        // signers are given out of order and are sorted like the sdk does
+        let tx = UnsignedTx::new(
+            vec![msg.clone()],
+            0,
+            "",
+            fee.clone(),
+            "juno-1",
+            vec![payer_signer.clone(), sender_signer.clone()],
+        )
+        .unwrap();
+        assert_eq!(tx.signers, vec![sender_signer.clone(), payer_signer]);
+        assert!(UnsignedTx::new(vec![msg], 0, "", fee, "juno-1", vec![sender_signer]).is_err());
+
+        let payer_signature = tx.sign(&payer).await.unwrap();
+        assert!(tx.combine([payer_signature.clone()]).is_err());
+
+        // the sender signs the exported sign bytes out of process
+        let sender_bytes = tx.sign_bytes(&tx.signers[0].address).unwrap();
+        let sender_signature = PartialSignature {
+            public_key: sender.public_key().await.unwrap(),
+            signature: sender.sign(&sender_bytes).await.unwrap(),
+        };
+
+        let raw: TxRaw = tx
+            .combine([payer_signature.clone(), sender_signature.clone()])
+            .unwrap()
+            .into();
+        assert_eq!(
+            raw.signatures,
+            vec![sender_signature.signature, payer_signature.signature]
+        );
+        let auth_info = AuthInfo::decode(raw.auth_info_bytes.as_slice()).unwrap();
+        assert_eq!(auth_info.signer_infos.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn sign_offline_from_json() {
+        let key = LocalSigner::from_raw(&[1; 32])
+            .unwrap()
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        let signer = TxSigner::from_public_key(
+            key.public_key().await.unwrap(),
+            KeyAlgorithm::Secp256k1,
+            "juno",
+            12,
+            3,
+        )
+        .unwrap();
+
+        let coin = Coin {
+            denom: "ujuno".parse().unwrap(),
+            amount: 100,
+        };
+        let msg = SendRequest {
+            from: signer.address.clone(),
+            to: signer.address.clone(),
+            amounts: vec![coin.clone()],
+        };
+        let fee = Fee::new(coin, 100_000u64, None, None);
+        let tx =
+            UnsignedTx::new(vec![msg], 0, "offline", fee, "juno-1", vec![signer.clone()]).unwrap();
+
+        // online box exports, air-gapped box imports with the same account number and sequence
+        let exported = serde_json::to_string(&tx.to_json().unwrap()).unwrap();
+        let imported: TxJson = serde_json::from_str(&exported).unwrap();
+        assert!(imported.auth_info.signer_infos.is_empty());
+        let offline = UnsignedTx::from_json(&imported, "juno-1", vec![signer]).unwrap();
+        assert_eq!(offline, tx);
+
+        let signature = offline.sign(&key).await.unwrap();
+        let signed = offline.combine_json([signature.clone()]).unwrap();
+        assert_eq!(signed.auth_info.signer_infos[0].sequence, "3");
+        assert_eq!(signed.to_raw().unwrap(), tx.combine([signature]).unwrap());
+    }
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use cosm_utils::{
+    chain::{
+        coin::{Coin, Denom},
+        request::PaginationResponse,
+    },
+    clients::recording::{RecordingClient, ReplayClient},
+    modules::{auth::model::Address, bank::api::BankQuery},
+};
+use tendermint_rpc::WebSocketClient;
+
+const FIXTURE: &str = "tests/fixtures/bank_queries.json";
+const ADDRESS: &str = "noria1ds0jnp7ful8hxmkstr5d2gxm28d8l0ecuff2v9";
+
+fn coin(denom: &str, amount: u128) -> Coin {
+    Coin {
+        denom: Denom::from_str(denom).unwrap(),
+        amount,
+    }
+}
+
+#[tokio::test]
+async fn replays_recorded_bank_queries() {
+    let client = ReplayClient::from_file(FIXTURE).unwrap();
+    let address = Address::from_str(ADDRESS).unwrap();
+
+    let balance = client
+        .bank_query_balance(address.clone(), Denom::from_str("ucrd").unwrap(), None)
+        .await
+        .unwrap();
+    assert_eq!(balance.value.balance, coin("ucrd", 9876543));
+    assert_eq!(balance.response.height.value(), 1843120);
+
+    let balances = client
+        .bank_query_balances(address, None, None)
+        .await
+        .unwrap()
+        .value;
+    assert_eq!(
+        balances.balances,
+        vec![
+            coin(&format!("factory/{ADDRESS}/ulp"), 500),
+            coin("ucrd", 9876543),
+        ]
+    );
+    assert_eq!(
+        balances.next,
+        Some(PaginationResponse {
+            next_key: vec![],
+            total: 2,
+        })
+    );
+
+    assert_eq!(client.remaining(), 0);
+}
+
+/// Re-records the fixture against a local node with `cargo test -- --ignored record`.
+/// The expected balances above have to be updated afterwards.
+#[ignore]
+#[tokio::test]
+async fn record_bank_queries() {
+    let (client, driver) = WebSocketClient::new("ws://localhost:26657/websocket")
+        .await
+        .unwrap();
+    let _handle = tokio::spawn(async move { driver.run().await });
+    let client = RecordingClient::new(client);
+    let address = Address::from_str(ADDRESS).unwrap();
+
+    client
+        .bank_query_balance(address.clone(), Denom::from_str("ucrd").unwrap(), None)
+        .await
+        .unwrap();
+    client
+        .bank_query_balances(address, None, None)
+        .await
+        .unwrap();
+
+    client.save(FIXTURE).unwrap();
+}
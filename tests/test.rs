@@ -22,22 +22,19 @@ async fn test() {
     let mnemonic = "evoke another library napkin rich clutch evil hungry supreme smart idea discover admit remain high torch dumb immense economy truck episode coral way pupil";
     let address = "noria1ds0jnp7ful8hxmkstr5d2gxm28d8l0ecuff2v9";
     let other_address = "noria1ya34jc44vvqzdhmwnfhkax7v4l3sj3stkwy9h5";
-    let key = UserKey {
-        name: "test".to_string(),
-        key: Key::Mnemonic(mnemonic.to_string()),
-    };
+    let key = UserKey::new("test", Key::Mnemonic(mnemonic.to_string()));
     // let rpc_endpoint = "http://localhost:26657";
     let websocket_endpoint = "ws://localhost:26657/websocket";
     // let websocket_endpoint = "wss://archive-rpc.noria.nextnet.zone/websocket";
     // let rpc_endpoint = "https://archive-rpc.noria.nextnet.zone";
-    let chain_cfg = ChainConfig {
-        denom: "ucrd".to_string(),
-        prefix: "noria".to_string(),
-        chain_id: "oasis-3".to_string(),
-        derivation_path: "m/44'/118'/0'/0/0".to_string(),
-        gas_price: 0.0025f64,
-        gas_adjustment: 2.0f64,
-    };
+    let chain_cfg = ChainConfig::new(
+        "ucrd",
+        "noria",
+        "oasis-3",
+        "m/44'/118'/0'/0/0",
+        0.0025f64,
+        2.0f64,
+    );
     // let http_client = HttpClient::new(rpc_endpoint).unwrap();
     let (client, driver) = WebSocketClient::new(websocket_endpoint).await.unwrap();
     let _handle = tokio::spawn(async move { driver.run().await });